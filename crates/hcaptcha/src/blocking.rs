@@ -0,0 +1,297 @@
+// SPDX-FileCopyrightText: 2022 jerusdp
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Blocking Hcaptcha Client
+//!
+//! The blocking [Client] verifies a request without an async runtime in the
+//! calling code, for command line tools and synchronous web frameworks. It
+//! wraps the async [crate::Client] and runs each verification to completion
+//! on the calling thread. Requests are sent with the [UreqTransport] and
+//! timeouts are waited for with the [ThreadTimer](crate::ThreadTimer), so
+//! no async runtime is started.
+//!
+//! Create the client once and reuse it so that its connections are reused.
+//!
+//! # Example
+//!
+//!```no_run
+//!     use hcaptcha::blocking::Client;
+//!     use hcaptcha::{Captcha, Request};
+//!
+//! # fn main() -> Result<(), hcaptcha::Error> {
+//! #   let secret = "0x123456789abcde0f123456789abcdef012345678";
+//!     let client = Client::new();
+//!
+//!     let captcha = Captcha::new("response_token")?;
+//!     let request = Request::new(secret, captcha)?;
+//!     let response = client.verify(request)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{Captcha, Error, Request, Response, UreqTransport};
+use std::future::Future;
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+
+/// Client to submit a request to a Hcaptcha validation endpoint and wait
+/// for the response.
+///
+/// The client is configured with the async [crate::Client] builder methods
+/// and converted with [From]. The transport of the async client is
+/// replaced by a [UreqTransport], as the other transports need an async
+/// runtime.
+///
+/// The verification methods block the calling thread until the response
+/// has been read. They may be called from within an async runtime, but
+/// block a thread of the runtime while they wait; use the async
+/// [crate::Client] there instead.
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[derive(Debug)]
+pub struct Client {
+    /// Async client that makes the requests.
+    inner: crate::Client,
+}
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl Default for Client {
+    fn default() -> Client {
+        Client::new()
+    }
+}
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl From<crate::Client> for Client {
+    fn from(inner: crate::Client) -> Client {
+        Client {
+            inner: inner.set_transport(UreqTransport::new()),
+        }
+    }
+}
+
+impl Client {
+    /// Create a new blocking Hcaptcha client to connect to the default
+    /// endpoint.
+    ///
+    /// # Example
+    ///
+    /// ```
+    ///     use hcaptcha::blocking::Client;
+    ///     let client = Client::new();
+    /// ```
+    pub fn new() -> Client {
+        Client::from(crate::Client::new())
+    }
+
+    /// Create a new blocking Hcaptcha client to connect to the endpoint at
+    /// `url`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), url::ParseError> {
+    ///     use hcaptcha::blocking::Client;
+    ///     let client = Client::new_with("https://api.hcaptcha.com/siteverify")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `url` cannot be parsed.
+    pub fn new_with(url: &str) -> Result<Client, url::ParseError> {
+        Ok(Client::from(crate::Client::new_with(url)?))
+    }
+
+    /// Verify the client token with the Hcaptcha API and wait for the
+    /// response.
+    ///
+    /// This is the blocking equivalent of [crate::Client::verify_request].
+    ///
+    /// # Errors
+    ///
+    /// Returns the error returned by the async client.
+    ///
+    /// # Logging
+    ///
+    /// If the `trace` feature is enabled a debug level span is set for the
+    /// method and an event logs the response.
+    ///
+    #[cfg_attr(
+        feature = "trace",
+        tracing::instrument(
+            name = "Request blocking verification from hcaptcha.",
+            skip(self),
+            level = "debug"
+        )
+    )]
+    pub fn verify(&self, request: Request) -> Result<Response, Error> {
+        self.block_on(self.inner.verify_request(request))
+    }
+
+    /// Verify the client token using the secret from the site registry or
+    /// the secret provider and wait for the response.
+    ///
+    /// This is the blocking equivalent of [crate::Client::verify_captcha].
+    ///
+    /// # Errors
+    ///
+    /// Returns the error returned by the async client.
+    ///
+    /// # Logging
+    ///
+    /// If the `trace` feature is enabled a debug level span is set for the
+    /// method and an event logs the response. The secret is not logged.
+    ///
+    #[cfg_attr(
+        feature = "trace",
+        tracing::instrument(
+            name = "Request blocking verification from hcaptcha with provided secret.",
+            skip(self),
+            level = "debug"
+        )
+    )]
+    pub fn verify_captcha(&self, captcha: Captcha) -> Result<Response, Error> {
+        self.block_on(self.inner.verify_captcha(captcha))
+    }
+
+    /// The async client that makes the requests.
+    pub(crate) fn inner(&self) -> &crate::Client {
+        &self.inner
+    }
+
+    /// Run the future to completion on the calling thread.
+    pub(crate) fn block_on<F: Future>(&self, future: F) -> F::Output {
+        let mut future = pin!(future);
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+            thread::park();
+        }
+    }
+}
+
+/// Wakes the thread blocked on a future by unparking it.
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::{assert_err, assert_ok};
+    use serde_json::json;
+    use wiremock::matchers::{body_string_contains, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const SECRET: &str = "0x123456789abcde0f123456789abcdef012345678";
+
+    async fn mock_server(success: bool) -> MockServer {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .and(body_string_contains("response=response_token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "success": success,
+                "error-codes": if success { json!([]) } else { json!(["invalid-input-response"]) },
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        mock_server
+    }
+
+    fn request() -> Request {
+        Request::new(SECRET, Captcha::new("response_token").unwrap()).unwrap()
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn blocking_verify_waits_for_the_response() {
+        let mock_server = mock_server(true).await;
+        let uri = format!("{}/siteverify", mock_server.uri());
+
+        // The mock server runs on the test runtime while the blocking
+        // client runs on a thread outside it.
+        let response = tokio::task::spawn_blocking(move || {
+            let client = Client::new_with(&uri).unwrap();
+            client.verify(request())
+        })
+        .await
+        .unwrap();
+
+        assert_ok!(&response);
+        assert!(response.unwrap().success());
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn blocking_verify_returns_the_error_codes() {
+        let mock_server = mock_server(false).await;
+        let uri = format!("{}/siteverify", mock_server.uri());
+
+        let response = tokio::task::spawn_blocking(move || {
+            let client = Client::from(crate::Client::new_with(&uri).unwrap());
+            client.verify(request())
+        })
+        .await
+        .unwrap();
+
+        assert_err!(&response);
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn blocking_verify_inside_a_runtime_waits_for_the_response() {
+        let mock_server = mock_server(true).await;
+        let uri = format!("{}/siteverify", mock_server.uri());
+
+        // The mock server keeps running on the other worker threads.
+        let client = Client::new_with(&uri).unwrap();
+        let response = client.verify(request());
+
+        assert_ok!(&response);
+        assert!(response.unwrap().success());
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[test]
+    fn blocking_verify_without_a_runtime_waits_for_the_response() {
+        // The mock server runs on its own runtime; the client does not.
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let mock_server = runtime.block_on(mock_server(true));
+        let uri = format!("{}/siteverify", mock_server.uri());
+
+        let client = Client::new_with(&uri).unwrap();
+        let response = client.verify(request());
+
+        assert_ok!(&response);
+        runtime.block_on(mock_server.verify());
+    }
+
+    #[test]
+    fn blocking_verify_captcha_without_secret_is_missing_secret() {
+        let client = Client::new();
+        match client.verify_captcha(Captcha::new("response_token").unwrap()) {
+            Err(Error::Codes(codes)) => assert!(codes.contains(&crate::Code::MissingSecret)),
+            _ => panic!("Expected the missing secret code"),
+        }
+    }
+}
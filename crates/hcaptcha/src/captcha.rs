@@ -181,6 +181,56 @@ impl Captcha {
         Ok(self.clone())
     }
 
+    /// Update the remoteip field in Captcha from an ip address.
+    ///
+    /// # Input
+    ///
+    /// remoteip - An [IpAddr](std::net::IpAddr), [Ipv4Addr](std::net::Ipv4Addr),
+    /// [Ipv6Addr](std::net::Ipv6Addr) or [SocketAddr](std::net::SocketAddr)
+    /// for the client. The port and any IPv6 scope id of a socket address
+    /// are discarded.
+    ///
+    /// # Output
+    ///
+    /// The field is set to Some(remoteip). As the address has already been
+    /// parsed this method cannot fail.
+    ///
+    /// # Example
+    ///
+    /// ```
+    ///     use hcaptcha::Captcha;
+    ///     use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+    /// # fn main() -> Result<(), hcaptcha::Error> {
+    ///     let peer = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 10, 20, 10)), 54321);
+    ///
+    ///     let captcha = Captcha::new("thisisthelonglistofcharactersthatformsaresponse")?
+    ///                     .set_remoteip_addr(peer);
+    ///
+    ///     if let Some(ip) = captcha.remoteip() {
+    ///         assert_eq!(peer.ip(), ip.ip());
+    ///     };
+    /// # Ok(())
+    /// # }
+    /// ```
+    /// # Logging
+    ///
+    /// If the tracing feature is enabled a debug level span is set for the
+    /// method.
+    ///
+    #[cfg_attr(
+        feature = "trace",
+        tracing::instrument(
+            name = "Update remoteip field in Captcha from ip address.",
+            skip(remoteip),
+            level = "debug"
+        )
+    )]
+    pub fn set_remoteip_addr(&mut self, remoteip: impl Into<Remoteip>) -> Self {
+        self.remoteip = Some(remoteip.into());
+
+        self.clone()
+    }
+
     /// Update the remoteip field in Captcha.
     ///
     /// # Input
@@ -257,6 +307,53 @@ impl Captcha {
         Ok(self.clone())
     }
 
+    /// Update the sitekey field in Captcha from a uuid.
+    ///
+    /// # Input
+    ///
+    /// sitekey - The sitekey as a [Uuid](uuid::Uuid)
+    ///
+    /// # Output
+    ///
+    /// The field is set to Some(sitekey). As a uuid is always a valid
+    /// sitekey this method cannot fail.
+    ///
+    /// # Example
+    ///
+    /// ```
+    ///     use hcaptcha::Captcha;
+    ///     use uuid::Uuid;
+    /// # fn main() -> Result<(), hcaptcha::Error> {
+    ///     let sitekey = Uuid::new_v4();
+    ///
+    ///     let captcha = Captcha::new("thisisthelonglistofcharactersthatformsaresponse")?
+    ///                     .set_sitekey_uuid(sitekey);
+    ///
+    ///     if let Some(sk) = captcha.sitekey() {
+    ///         assert_eq!(sitekey.to_string(), sk.to_string());
+    ///     };
+    /// # Ok(())
+    /// # }
+    /// ```
+    /// # Logging
+    ///
+    /// If the tracing feature is enabled a debug level span is set for the
+    /// method.
+    ///
+    #[cfg_attr(
+        feature = "trace",
+        tracing::instrument(
+            name = "Update sitekey field in Captcha from uuid.",
+            skip(sitekey),
+            level = "debug"
+        )
+    )]
+    pub fn set_sitekey_uuid(&mut self, sitekey: impl Into<Sitekey>) -> Self {
+        self.sitekey = Some(sitekey.into());
+
+        self.clone()
+    }
+
     /// Return the value of the response field.
     ///
     /// # Output
@@ -364,7 +461,7 @@ impl Captcha {
         tracing::instrument(name = "Get remoteip field.", level = "debug")
     )]
     pub fn remoteip(&self) -> Option<Remoteip> {
-        self.remoteip.clone()
+        self.remoteip
     }
 
     /// Get the value of the sitekey field.
@@ -432,6 +529,8 @@ mod tests {
     use claims::{assert_err, assert_none, assert_ok, assert_some};
     use rand::distr::Alphanumeric;
     use rand::{rng, RngExt};
+    use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+    use uuid::Uuid;
 
     fn random_response() -> String {
         let mut rng = rng();
@@ -538,6 +637,42 @@ mod tests {
         assert_none!(captcha.remoteip());
     }
 
+    #[test]
+    fn remoteip_set_from_socket_address() {
+        let addr = SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), 443);
+        let captcha = Captcha::new("response_string")
+            .unwrap()
+            .set_remoteip_addr(addr);
+
+        assert_eq!(captcha.remoteip().unwrap().ip(), addr.ip());
+    }
+
+    #[test]
+    fn remoteip_from_ip_addr_matches_remoteip_from_string() {
+        let remoteip = mockd::internet::ipv4_address();
+        let ip: IpAddr = remoteip.parse().unwrap();
+
+        let from_str = Captcha::new("response_string")
+            .unwrap()
+            .set_remoteip(&remoteip)
+            .unwrap();
+        let from_ip = Captcha::new("response_string")
+            .unwrap()
+            .set_remoteip_addr(ip);
+
+        assert_eq!(from_str.remoteip(), from_ip.remoteip());
+    }
+
+    #[test]
+    fn sitekey_set_from_uuid() {
+        let sitekey = Uuid::new_v4();
+        let captcha = Captcha::new("response_string")
+            .unwrap()
+            .set_sitekey_uuid(sitekey);
+
+        assert_eq!(captcha.sitekey().unwrap().to_string(), sitekey.to_string());
+    }
+
     fn get_captcha() -> (String, Captcha) {
         let remoteip = mockd::internet::ipv4_address();
         let response = random_response();
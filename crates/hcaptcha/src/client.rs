@@ -11,6 +11,8 @@
 //! The url for the API is stored in the url field of the struct.
 //! A default url is stored in the const VERIFY_URL.
 //! The new_with method allows the specification of an alternative url.
+//! Further urls added with add_failover_url are tried in turn if the
+//! endpoint cannot be reached.
 //!
 //! # Examples
 //! Create client to connect to default API endpoint.
@@ -60,16 +62,48 @@
 // const CYAN: &str = "\u{001b}[35m";
 // const RESET: &str = "\u{001b}[0m";
 
+use crate::Captcha;
+use crate::Code;
 use crate::Error;
 use crate::Request;
 use crate::Response;
-use reqwest::Url;
+use crate::Secret;
+use crate::SecretProvider;
+use crate::SiteRegistry;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use crate::Timer;
+use crate::TokenStore;
+use crate::Transport;
+#[cfg(feature = "verification-cache")]
+use crate::{Remoteip, VerificationCache, VerificationPass};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use async_lock::Semaphore;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use url::Url;
 // #[cfg(target_arch = "wasm32")]
 // use tokio::runtime;
 
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+mod circuit_breaker;
+mod endpoints;
+mod failure_mode;
 mod form;
+mod privacy;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+mod rate_limit;
 
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub use circuit_breaker::{CircuitBreaker, CircuitState};
+pub use endpoints::EndpointSelection;
+use endpoints::Endpoints;
+pub use failure_mode::FailureMode;
 use form::Form;
+pub use privacy::RemoteIpPrivacy;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub use rate_limit::{Quota, RateLimiter};
 
 /// Endpoint url for the Hcaptcha siteverify API.
 pub const VERIFY_URL: &str = "https://hcaptcha.com/siteverify";
@@ -78,10 +112,43 @@ pub const VERIFY_URL: &str = "https://hcaptcha.com/siteverify";
 #[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
 #[derive(Debug)]
 pub struct Client {
-    /// HTTP Client to submit request to endpoint and read the response.
-    client: reqwest::Client,
-    /// Url for the endpoint.
-    url: Url,
+    /// HTTP transport to submit request to endpoint and read the response.
+    transport: Arc<dyn Transport>,
+    /// Urls for the endpoint in the order to try them.
+    endpoints: Endpoints,
+    /// How much of the remote ip to send to the endpoint.
+    remoteip_privacy: RemoteIpPrivacy,
+    /// Outcome of a verification when the endpoint cannot be reached.
+    failure_mode: FailureMode,
+    /// Provider for the secret used by [Client::verify_captcha].
+    secret_provider: Option<Arc<dyn SecretProvider>>,
+    /// Registry of the secret and policy for each sitekey.
+    site_registry: Option<Arc<SiteRegistry>>,
+    /// Store of the tokens that have been verified.
+    token_store: Option<Arc<dyn TokenStore>>,
+    /// Limit on the rate of verifications sent to the endpoint.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    rate_limiter: Option<RateLimiter>,
+    /// Limit on the requests in flight and how long to wait for one to
+    /// complete.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    concurrency_limit: Option<(Semaphore, Duration)>,
+    /// Timer used to wait for timeouts, if there is one for the target.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    timer: Option<Arc<dyn Timer>>,
+    /// Circuit breaker to fail fast while the endpoint is unavailable.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    circuit_breaker: Option<CircuitBreaker>,
+    /// How long to wait for a connection before hedging the request.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    hedge_delay: Option<Duration>,
+    /// Cache to issue signed passes for successful verifications.
+    #[cfg(feature = "verification-cache")]
+    verification_cache: Option<VerificationCache>,
+    /// Secret to retry with if the endpoint rejects the request secret.
+    fallback_secret: Option<Secret>,
+    /// Number of requests verified with the fallback secret.
+    fallback_secret_uses: AtomicU64,
 }
 
 #[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
@@ -110,8 +177,27 @@ impl Client {
     #[cfg_attr(docsrs, allow(rustdoc::bare_urls))]
     pub fn new() -> Client {
         Client {
-            client: reqwest::Client::new(),
-            url: Url::parse(VERIFY_URL).expect("API url string corrupt"),
+            transport: crate::transport::default_transport(),
+            endpoints: Endpoints::new(Url::parse(VERIFY_URL).expect("API url string corrupt")),
+            remoteip_privacy: RemoteIpPrivacy::default(),
+            failure_mode: FailureMode::default(),
+            secret_provider: None,
+            site_registry: None,
+            token_store: None,
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            rate_limiter: None,
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            concurrency_limit: None,
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            timer: crate::timer::default_timer(),
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            circuit_breaker: None,
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            hedge_delay: None,
+            #[cfg(feature = "verification-cache")]
+            verification_cache: None,
+            fallback_secret: None,
+            fallback_secret_uses: AtomicU64::new(0),
         }
     }
 
@@ -130,8 +216,27 @@ impl Client {
     /// ```
     pub fn new_with(url: &str) -> Result<Client, url::ParseError> {
         Ok(Client {
-            client: reqwest::Client::new(),
-            url: Url::parse(url)?,
+            transport: crate::transport::default_transport(),
+            endpoints: Endpoints::new(Url::parse(url)?),
+            remoteip_privacy: RemoteIpPrivacy::default(),
+            failure_mode: FailureMode::default(),
+            secret_provider: None,
+            site_registry: None,
+            token_store: None,
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            rate_limiter: None,
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            concurrency_limit: None,
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            timer: crate::timer::default_timer(),
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            circuit_breaker: None,
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            hedge_delay: None,
+            #[cfg(feature = "verification-cache")]
+            verification_cache: None,
+            fallback_secret: None,
+            fallback_secret_uses: AtomicU64::new(0),
         })
     }
 
@@ -154,35 +259,584 @@ impl Client {
     /// # }
     /// ```
     pub fn set_url(mut self, url: &str) -> Result<Self, Error> {
-        self.url = Url::parse(url)?;
+        self.endpoints.set_primary(Url::parse(url)?);
         Ok(self)
     }
 
+    /// The url of the primary endpoint.
+    pub(crate) fn url(&self) -> &Url {
+        self.endpoints.primary()
+    }
+
+    /// Add a failover url.
+    ///
+    /// If the endpoint cannot be reached, times out, or returns a server
+    /// error or `429 Too Many Requests`, the request is sent to the next
+    /// url. Any other error status is returned without failing over. The urls are tried in the order
+    /// they were added after the url set with [Client::new_with] or
+    /// [Client::set_url], unless the order is changed with
+    /// [Client::set_endpoint_selection].
+    ///
+    /// A request that failed after it reached an endpoint may have used
+    /// the token, in which case the next endpoint rejects it as already
+    /// seen.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), hcaptcha::Error> {
+    ///     use hcaptcha::Client;
+    ///
+    ///     let client = Client::new_with("https://api.hcaptcha.com/siteverify")?
+    ///         .add_failover_url("https://hcaptcha.com/siteverify")?;
+    /// #    Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// [Error::Url] is returned if the url cannot be parsed.
+    pub fn add_failover_url(mut self, url: &str) -> Result<Self, Error> {
+        self.endpoints.push(Url::parse(url)?);
+        Ok(self)
+    }
+
+    /// Set how the order to try the endpoints is chosen.
+    ///
+    /// The default, [EndpointSelection::Ordered], tries the urls in the
+    /// order they were added.
+    pub fn set_endpoint_selection(mut self, selection: EndpointSelection) -> Self {
+        self.endpoints.set_selection(selection);
+        self
+    }
+
+    /// Set the HTTP transport used to post the form to the endpoint.
+    ///
+    /// Unless a transport is set, the `ReqwestTransport` is used if the
+    /// `reqwest` feature is enabled, as it is by default. Otherwise the
+    /// `UreqTransport` is used with the `ureq` feature, or the
+    /// `WasiHttpTransport` on wasi targets with the `wasi-http` feature.
+    /// Without any of these each request fails with [Error::Transport]
+    /// until a transport is set. The `hyper` feature provides a further
+    /// transport, or another HTTP stack can be used by implementing
+    /// [Transport].
+    ///
+    /// # Example
+    /// ```
+    ///     use hcaptcha::{Client, ReqwestTransport};
+    ///
+    ///     let client = Client::new()
+    ///         .set_transport(ReqwestTransport::new(reqwest::Client::new()));
+    /// ```
+    pub fn set_transport(mut self, transport: impl Transport + 'static) -> Self {
+        self.transport = Arc::new(transport);
+        self
+    }
+
+    /// Set the remote ip privacy mode.
+    ///
+    /// Reduce or remove the client's ip address before the request is
+    /// submitted to the hcaptcha API. The default, [RemoteIpPrivacy::Full],
+    /// sends the ip address as supplied in the [Request].
+    ///
+    /// # Example
+    /// Initialise client to send only the network prefix of the remote ip
+    /// ```
+    ///     use hcaptcha::{Client, RemoteIpPrivacy};
+    ///
+    ///     let client = Client::new()
+    ///                        .set_remoteip_privacy(RemoteIpPrivacy::Truncate);
+    /// ```
+    ///
+    /// # Logging
+    ///
+    /// If the `trace` feature is enabled the privacy mode is recorded in
+    /// the span for each request.
+    ///
+    pub fn set_remoteip_privacy(mut self, privacy: RemoteIpPrivacy) -> Self {
+        self.remoteip_privacy = privacy;
+        self
+    }
+
+    /// Set the failure mode for verifications.
+    ///
+    /// The failure mode controls the outcome of a verification when the
+    /// endpoint cannot be reached. It can be set for a single request with
+    /// [Request::set_failure_mode].
+    ///
+    /// # Example
+    /// ```
+    ///     use hcaptcha::{Client, FailureMode};
+    ///
+    ///     let client = Client::new()
+    ///         .set_failure_mode(FailureMode::FailOpen);
+    /// ```
+    pub fn set_failure_mode(mut self, failure_mode: FailureMode) -> Self {
+        self.failure_mode = failure_mode;
+        self
+    }
+
+    /// Set the secret provider.
+    ///
+    /// The provider supplies the secret for requests verified with
+    /// [Client::verify_captcha], so the secret does not need to be passed
+    /// in with each request.
+    ///
+    /// # Example
+    /// Initialise client to read the secret from a mounted secret file
+    /// ```
+    ///     use hcaptcha::{CachedSecretProvider, Client, FileSecretProvider};
+    ///     use std::time::Duration;
+    ///
+    ///     let client = Client::new()
+    ///                        .set_secret_provider(CachedSecretProvider::new(
+    ///                            FileSecretProvider::new("/run/secrets/hcaptcha_secret"),
+    ///                            Duration::from_secs(60),
+    ///                        ));
+    /// ```
+    pub fn set_secret_provider(mut self, provider: impl SecretProvider + 'static) -> Self {
+        self.secret_provider = Some(Arc::new(provider));
+        self
+    }
+
+    /// Set the site registry.
+    ///
+    /// Requests verified with [Client::verify_captcha] use the secret
+    /// registered for the sitekey in the [Captcha] and the response is
+    /// checked against the policy for the site. The registry can be shared
+    /// with other clients by passing an `Arc<SiteRegistry>`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # fn main() -> Result<(), hcaptcha::Error> {
+    ///     use hcaptcha::{Client, SiteRegistry};
+    ///
+    ///     let client = Client::new()
+    ///         .set_site_registry(SiteRegistry::from_file("/etc/hcaptcha/sites.json")?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_site_registry(mut self, registry: impl Into<Arc<SiteRegistry>>) -> Self {
+        self.site_registry = Some(registry.into());
+        self
+    }
+
+    /// Set the store of verified tokens to protect against replay.
+    ///
+    /// Before a request is sent the token is recorded in the store. If it
+    /// is already in the store the request is rejected with
+    /// [Code::InvalidAlreadySeen] without calling the Hcaptcha API, so a
+    /// token verified concurrently is only sent once. A token that fails
+    /// verification, including when the endpoint is unavailable, is removed
+    /// from the store so that it can be verified again.
+    ///
+    /// # Example
+    /// ```
+    ///     use hcaptcha::{Client, MemoryTokenStore};
+    ///     use std::time::Duration;
+    ///
+    ///     let client = Client::new()
+    ///         .set_token_store(MemoryTokenStore::new(Duration::from_secs(300)));
+    /// ```
+    pub fn set_token_store(mut self, store: impl TokenStore + 'static) -> Self {
+        self.token_store = Some(Arc::new(store));
+        self
+    }
+
+    /// Set the rate limiter for verifications.
+    ///
+    /// Verifications over the limit are rejected with [Error::RateLimited]
+    /// without calling the Hcaptcha API, so a flood of bad tokens does not
+    /// use up the rate limit of the API or delay other verifications.
+    ///
+    /// # Example
+    /// ```
+    ///     use hcaptcha::{Client, Quota, RateLimiter};
+    ///
+    ///     let client = Client::new()
+    ///         .set_rate_limiter(RateLimiter::new().set_per_ip(Quota::per_minute(10)));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn set_rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Hedge requests that are slow to connect.
+    ///
+    /// If the connection to the endpoint is not established within `delay`
+    /// the attempt is abandoned and the request is sent to the next
+    /// endpoint, or to the same endpoint again without the timeout if there
+    /// is no next one. The request is only hedged while connecting, before
+    /// any of the form has been sent, so the token cannot be used up by the
+    /// abandoned attempt. A request that is slow to answer once connected is
+    /// not hedged.
+    ///
+    /// The connect timeout is applied by the transport with
+    /// [Transport::post_form_with_connect_timeout]; requests sent with a
+    /// transport that does not support it are not hedged.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), hcaptcha::Error> {
+    ///     use hcaptcha::Client;
+    ///     use std::time::Duration;
+    ///
+    ///     let client = Client::new_with("https://api.hcaptcha.com/siteverify")?
+    ///         .add_failover_url("https://hcaptcha.com/siteverify")?
+    ///         .set_hedge_delay(Duration::from_millis(500));
+    /// #    Ok(())
+    /// # }
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn set_hedge_delay(mut self, delay: Duration) -> Self {
+        self.hedge_delay = Some(delay);
+        self
+    }
+
+    /// Limit the number of requests in flight to the endpoint.
+    ///
+    /// Once `max_in_flight` requests are in flight further verifications
+    /// wait for a request to complete. A verification that waits longer
+    /// than `queue_timeout` is rejected with [Error::Overloaded]. On wasi
+    /// targets without the `tokio` feature there is no default timer, so
+    /// verifications wait without a queue timeout unless a timer is set
+    /// with [Client::set_timer].
+    ///
+    /// # Example
+    /// ```
+    ///     use hcaptcha::Client;
+    ///     use std::time::Duration;
+    ///
+    ///     let client = Client::new()
+    ///         .set_concurrency_limit(50, Duration::from_millis(500));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn set_concurrency_limit(mut self, max_in_flight: usize, queue_timeout: Duration) -> Self {
+        self.concurrency_limit = Some((Semaphore::new(max_in_flight), queue_timeout));
+        self
+    }
+
+    /// Set the timer used to wait for timeouts, such as the queue timeout
+    /// of the concurrency limit.
+    ///
+    /// The `ThreadTimer`, which runs under any async runtime, is used by
+    /// default. On wasi targets, which have no threads, the `TokioTimer` is
+    /// used by default with the `tokio` feature, and otherwise there is no
+    /// timer until one is set.
+    ///
+    /// # Example
+    /// ```
+    ///     use hcaptcha::{Client, ThreadTimer};
+    ///     use std::time::Duration;
+    ///
+    ///     let client = Client::new()
+    ///         .set_concurrency_limit(50, Duration::from_millis(500))
+    ///         .set_timer(ThreadTimer);
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn set_timer(mut self, timer: impl Timer + 'static) -> Self {
+        self.timer = Some(Arc::new(timer));
+        self
+    }
+
+    /// Set the circuit breaker for the endpoint.
+    ///
+    /// While the circuit is open verifications are rejected with
+    /// [Error::CircuitOpen] without waiting for the endpoint to time out.
+    ///
+    /// # Example
+    /// ```
+    ///     use hcaptcha::{CircuitBreaker, Client};
+    ///     use std::time::Duration;
+    ///
+    ///     let client = Client::new()
+    ///         .set_circuit_breaker(CircuitBreaker::new(5, Duration::from_secs(30)));
+    /// ```
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn set_circuit_breaker(mut self, circuit_breaker: CircuitBreaker) -> Self {
+        self.circuit_breaker = Some(circuit_breaker);
+        self
+    }
+
+    /// The state of the circuit breaker, for use in health checks.
+    ///
+    /// Returns `None` if no circuit breaker has been set.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn circuit_state(&self) -> Option<CircuitState> {
+        self.circuit_breaker.as_ref().map(CircuitBreaker::state)
+    }
+
+    /// Set the cache to issue signed passes for successful verifications.
+    ///
+    /// After a request is verified successfully a pass is issued and
+    /// returned by [Response::pass]. Later steps of a flow present the pass
+    /// to [Client::verify_pass] instead of verifying a new token.
+    ///
+    /// # Example
+    /// ```
+    ///     use hcaptcha::{Client, VerificationCache};
+    ///     use std::time::Duration;
+    ///
+    ///     let key = b"a key of at least thirty two bytes";
+    ///     let client = Client::new()
+    ///         .set_verification_cache(VerificationCache::new(key, Duration::from_secs(600)));
+    /// ```
+    #[cfg(feature = "verification-cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "verification-cache")))]
+    pub fn set_verification_cache(mut self, cache: VerificationCache) -> Self {
+        self.verification_cache = Some(cache);
+        self
+    }
+
+    /// Verify a pass issued by the verification cache.
+    ///
+    /// The pass is verified offline. If the pass records a remote ip the
+    /// `remoteip` of the current request must be the same.
+    ///
+    /// # Errors
+    ///
+    /// [Code::InvalidPass] is returned if no verification cache has been set
+    /// or the pass is not valid, and [Code::ExpiredPass] if it has expired.
+    #[cfg(feature = "verification-cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "verification-cache")))]
+    pub fn verify_pass(
+        &self,
+        pass: &str,
+        remoteip: Option<Remoteip>,
+    ) -> Result<VerificationPass, Error> {
+        let Some(cache) = &self.verification_cache else {
+            let mut codes = HashSet::new();
+            codes.insert(Code::InvalidPass);
+            return Err(Error::Codes(codes));
+        };
+        cache.verify(pass, remoteip)
+    }
+
+    /// Set a fallback secret to support secret rotation.
+    ///
+    /// While a secret is being rotated a sitekey may be bound to either the
+    /// old or the new secret. If the endpoint rejects a request with
+    /// [Code::InvalidSecret] or [Code::SiteSecretMismatch] the request is
+    /// retried once with the fallback secret.
+    ///
+    /// Each retry that succeeds with the fallback secret is counted (see
+    /// [Client::fallback_secret_uses]) and, if the `trace` feature is
+    /// enabled, logged with an info level event. When the count stops
+    /// increasing the fallback secret is no longer in use and can be retired.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), hcaptcha::Error> {
+    ///     use hcaptcha::{Client, Secret};
+    ///
+    ///     let new_secret: Secret = "0x123456789abcde0f123456789abcdef012345678".parse()?;
+    ///     let old_secret: Secret = "0xabcdef0123456789abcdef0123456789abcdef01".parse()?;
+    ///
+    ///     let client = Client::new()
+    ///         .set_secret_provider(new_secret)
+    ///         .set_fallback_secret(old_secret);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_fallback_secret(mut self, secret: Secret) -> Self {
+        self.fallback_secret = Some(secret);
+        self
+    }
+
+    /// Number of requests that have been verified with the fallback secret.
+    pub fn fallback_secret_uses(&self) -> u64 {
+        self.fallback_secret_uses.load(Ordering::Relaxed)
+    }
+
     /// Internal method to handle the actual HTTP request and response processing.
     #[cfg_attr(
         feature = "trace",
         tracing::instrument(
             name = "Internal HTTP request to hCaptcha API.",
             skip(self),
+            fields(remoteip_privacy = ?self.remoteip_privacy),
             level = "debug"
         )
     )]
     async fn make_request(&self, request: Request) -> Result<Response, Error> {
-        let form: Form = request.into();
+        let captcha = request.captcha();
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        if let Some(rate_limiter) = &self.rate_limiter {
+            if !rate_limiter.check(captcha.remoteip) {
+                return Err(Error::RateLimited);
+            }
+        }
+        if let Some(store) = &self.token_store {
+            if !store.insert_if_absent(&captcha.response).await? {
+                #[cfg(feature = "trace")]
+                tracing::debug!("Token has already been verified");
+                let mut codes = HashSet::new();
+                codes.insert(Code::InvalidAlreadySeen);
+                return Err(Error::Codes(codes));
+            }
+        }
+
+        let failure_mode = request.failure_mode().unwrap_or(self.failure_mode);
+        let result = self.send(Form::new(request, self.remoteip_privacy)).await;
+
+        // The token was not verified, so release it to be verified again.
+        if let (Err(_), Some(store)) = (&result, &self.token_store) {
+            if let Err(_e) = store.remove(&captcha.response).await {
+                #[cfg(feature = "trace")]
+                tracing::warn!("Failed to remove the token from the store: {}", _e);
+            }
+        }
+        #[allow(unused_mut)]
+        let mut response = match result {
+            Err(e) if failure_mode == FailureMode::FailOpen && is_unreachable(&e) => {
+                #[cfg(feature = "trace")]
+                tracing::warn!("Endpoint unreachable, failing open: {}", e);
+                return Ok(Response::new_degraded());
+            }
+            result => result?,
+        };
+        #[cfg(feature = "verification-cache")]
+        if let Some(cache) = &self.verification_cache {
+            response.pass = Some(cache.issue(&captcha, &response)?);
+        }
+        Ok(response)
+    }
+
+    /// Send the form to the endpoint within the circuit breaker and
+    /// concurrency limit.
+    async fn send(&self, form: Form) -> Result<Response, Error> {
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        let attempt = match &self.circuit_breaker {
+            Some(circuit_breaker) => match circuit_breaker.attempt() {
+                Some(attempt) => Some(attempt),
+                None => {
+                    #[cfg(feature = "trace")]
+                    tracing::debug!("Circuit open, verification rejected");
+                    return Err(Error::CircuitOpen);
+                }
+            },
+            None => None,
+        };
+
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        let _permit = match &self.concurrency_limit {
+            Some((semaphore, queue_timeout)) => {
+                let permit = match &self.timer {
+                    Some(timer) => {
+                        crate::timer::timeout(&**timer, *queue_timeout, semaphore.acquire()).await
+                    }
+                    None => Some(semaphore.acquire().await),
+                };
+                match permit {
+                    Some(permit) => Some(permit),
+                    None => {
+                        #[cfg(feature = "trace")]
+                        tracing::debug!("Timed out waiting for a request in flight to complete");
+                        return Err(Error::Overloaded);
+                    }
+                }
+            }
+            None => None,
+        };
+
+        let result = self.verify_form(form).await;
+
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        if let Some(attempt) = attempt {
+            attempt.record(!matches!(&result, Err(e) if is_transient(e)));
+        }
+        result
+    }
+
+    async fn verify_form(&self, form: Form) -> Result<Response, Error> {
         #[cfg(feature = "trace")]
         tracing::debug!(
             "The form to submit to Hcaptcha API: {:?}",
             serde_urlencoded::to_string(&form).unwrap_or_else(|_| "form corrupted".to_owned())
         );
 
-        let response = self
-            .client
-            .post(self.url.clone())
-            .form(&form)
-            .send()
-            .await?
-            .json::<Response>()
-            .await?;
+        let result = self.submit(&form).await;
+
+        match (&result, &self.fallback_secret) {
+            (Err(Error::Codes(codes)), Some(fallback))
+                if rejected_secret(codes) && !form.uses_secret(fallback) =>
+            {
+                #[cfg(feature = "trace")]
+                tracing::debug!("Secret rejected, retrying with the fallback secret");
+                let response = self.submit(&form.with_secret(fallback)).await?;
+                self.fallback_secret_uses.fetch_add(1, Ordering::Relaxed);
+
+                #[cfg(feature = "trace")]
+                tracing::info!(
+                    fallback_secret_uses = self.fallback_secret_uses(),
+                    "Request verified with the fallback secret"
+                );
+                Ok(response)
+            }
+            _ => result,
+        }
+    }
+
+    async fn submit(&self, form: &Form) -> Result<Response, Error> {
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        let hedge_delay = self.hedge_delay;
+        #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+        let hedge_delay = None;
+
+        // A request that is slow to connect fails with a connect error and
+        // is hedged by failing over to the next endpoint.
+        let endpoints = self.endpoints.ordered();
+        let (last, earlier) = endpoints.split_last().expect("client has an endpoint");
+        for endpoint in earlier {
+            match endpoint
+                .timed(self.post(endpoint.url(), hedge_delay, form))
+                .await
+            {
+                Err(_e) if is_transient(&_e) => {
+                    #[cfg(feature = "trace")]
+                    tracing::warn!(url = %endpoint.url(), "Failing over from endpoint: {}", _e);
+                }
+                result => return result,
+            }
+        }
+        match last.timed(self.post(last.url(), hedge_delay, form)).await {
+            Err(_e) if hedge_delay.is_some() && is_connect_failure(&_e) => {
+                #[cfg(feature = "trace")]
+                tracing::info!(url = %last.url(), "Connection slow, hedging request: {}", _e);
+                last.timed(self.post(last.url(), None, form)).await
+            }
+            result => result,
+        }
+    }
+
+    /// Post the form to the endpoint, giving up if the connection is not
+    /// established within `connect_timeout` if there is one.
+    async fn post(
+        &self,
+        url: &Url,
+        connect_timeout: Option<Duration>,
+        form: &Form,
+    ) -> Result<Response, Error> {
+        let form = serde_urlencoded::to_string(form)?;
+        let response = match connect_timeout {
+            Some(timeout) => {
+                self.transport
+                    .post_form_with_connect_timeout(url, form, timeout)
+                    .await?
+            }
+            None => self.transport.post_form(url, form).await?,
+        };
+        if response.is_unavailable() {
+            return Err(Error::Status(response.status()));
+        }
+        // A body that is not a siteverify response did not come from the API.
+        // It is reported as the status when that is an error, otherwise as
+        // the parse error.
+        let response = match serde_json::from_slice::<Response>(response.body()) {
+            Ok(body) => body,
+            Err(_) if !response.is_success() => return Err(Error::Status(response.status())),
+            Err(e) => return Err(Error::Json(e)),
+        };
 
         #[cfg(feature = "trace")]
         tracing::debug!("The response is: {:?}", response);
@@ -414,12 +1068,122 @@ impl Client {
     pub async fn verify_request(&self, request: Request) -> Result<Response, Error> {
         self.make_request(request).await
     }
+
+    /// Verify the client token using the secret from the site registry or
+    /// the secret provider.
+    ///
+    /// If a site registry has been set with [Client::set_site_registry] and
+    /// the sitekey in the [Captcha] is registered, the secret for the site is
+    /// used and the response is checked against the policy for the site.
+    /// Otherwise the secret is resolved from the provider set with
+    /// [Client::set_secret_provider]. The secret is combined with the
+    /// [Captcha] to build the [Request].
+    ///
+    /// # Outputs
+    ///
+    /// This method returns [Response] if successful and [Error] if
+    /// unsuccessful. If no secret provider has been set the error
+    /// contains [Code::MissingSecret], or [Code::MissingSiteKey] or
+    /// [Code::InvalidSiteKey] if the sitekey is not in the site registry.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use hcaptcha::{Captcha, Client, EnvSecretProvider};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), hcaptcha::Error> {
+    /// let client = Client::new()
+    ///     .set_secret_provider(EnvSecretProvider::new("HCAPTCHA_SECRET"));
+    ///
+    /// for token in &["token1", "token2", "token3"] {
+    ///     let captcha = Captcha::new(token)?;
+    ///     let response = client.verify_captcha(captcha).await?;
+    ///     println!("Verification successful: {}", response.success());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Logging
+    ///
+    /// If the `trace` feature is enabled a debug level span is set for the
+    /// method and an event logs the response. The secret is not logged.
+    ///
+    #[cfg_attr(
+        feature = "trace",
+        tracing::instrument(
+            name = "Request verification from hcaptcha with provided secret.",
+            skip(self),
+            level = "debug"
+        )
+    )]
+    pub async fn verify_captcha(&self, captcha: Captcha) -> Result<Response, Error> {
+        if let Some(registry) = &self.site_registry {
+            if let Some(site) = captcha.sitekey.as_ref().and_then(|s| registry.site(s)) {
+                let request = Request::new_from_secret(site.secret().clone(), captcha);
+                let response = self.make_request(request).await?;
+                if !response.degraded() {
+                    site.policy().check(&response)?;
+                }
+                return Ok(response);
+            }
+        }
+        let Some(provider) = &self.secret_provider else {
+            let mut codes = HashSet::new();
+            match (&self.site_registry, &captcha.sitekey) {
+                (None, _) => codes.insert(Code::MissingSecret),
+                (Some(_), None) => codes.insert(Code::MissingSiteKey),
+                (Some(_), Some(_)) => codes.insert(Code::InvalidSiteKey),
+            };
+            return Err(Error::Codes(codes));
+        };
+        let secret = provider.secret().await?;
+        self.make_request(Request::new_from_secret(secret, captcha))
+            .await
+    }
+}
+
+/// Check if the endpoint rejected the secret used for the request.
+fn rejected_secret(codes: &HashSet<Code>) -> bool {
+    codes.contains(&Code::InvalidSecret) || codes.contains(&Code::SiteSecretMismatch)
+}
+
+/// Check if the error shows the endpoint is unavailable for now: the
+/// connection failed or timed out, or the endpoint answered with a server
+/// error or `429 Too Many Requests`.
+///
+/// Any other error, such as a `404` or a body that is not a siteverify
+/// response, is a misconfiguration and not transient.
+fn is_transient(error: &Error) -> bool {
+    match error {
+        #[cfg(all(feature = "reqwest", not(target_os = "wasi")))]
+        Error::Reqwest(e) => e.is_connect() || e.is_timeout(),
+        Error::Connect(_) | Error::Timeout(_) => true,
+        Error::Status(status) => *status == 429 || (500..600).contains(status),
+        _ => false,
+    }
+}
+
+/// Check if the error shows the connection to the endpoint could not be
+/// established, so none of the form was sent.
+fn is_connect_failure(error: &Error) -> bool {
+    match error {
+        #[cfg(all(feature = "reqwest", not(target_os = "wasi")))]
+        Error::Reqwest(e) => e.is_connect(),
+        Error::Connect(_) => true,
+        _ => false,
+    }
+}
+
+/// Check if the error shows that the endpoint cannot be reached.
+fn is_unreachable(error: &Error) -> bool {
+    is_transient(error) || matches!(error, Error::CircuitOpen)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{Code, Error};
+    use crate::{Code, Error, TransportResponse};
     use chrono::{TimeDelta, Utc};
     use claims::{assert_err, assert_ok};
     use rand::distr::Alphanumeric;
@@ -710,17 +1474,1022 @@ mod tests {
         assert!(logs_contain("The response is"));
     }
 
-    #[test]
-    fn test_success_response() {
-        let api_response = json!({
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    #[cfg_attr(feature = "trace", traced_test)]
+    async fn hcaptcha_mock_with_truncated_remoteip() {
+        let token = random_string(100);
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+        let request = Request::new_from_response(&secret, &token)
+            .unwrap()
+            .set_remoteip("192.0.2.123")
+            .unwrap();
+
+        let expected_body = format!("response={}&remoteip=192.0.2.0&secret={}", token, secret);
+
+        let response_template = ResponseTemplate::new(200).set_body_json(json!({
             "success": true,
-            "challenge_ts": "2020-11-11T23:27:00Z",
-            "hostname": "my-host.ie",
-            "credit": true,
-            "error-codes": [],
-            "score": null,
-            "score_reason": [],
-        });
+            "hostname": "test-host",
+        }));
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .and(body_string(&expected_body))
+            .respond_with(response_template)
+            .mount(&mock_server)
+            .await;
+        let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+
+        let client = Client::new_with(&uri)
+            .unwrap()
+            .set_remoteip_privacy(RemoteIpPrivacy::Truncate);
+        let response = client.verify_request(request).await;
+        assert_ok!(&response);
+        #[cfg(feature = "trace")]
+        assert!(logs_contain("remoteip_privacy=Truncate"));
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    #[cfg_attr(feature = "trace", traced_test)]
+    async fn hcaptcha_mock_with_omitted_remoteip() {
+        let token = random_string(100);
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+        let request = Request::new_from_response(&secret, &token)
+            .unwrap()
+            .set_remoteip(&mockd::internet::ipv6_address())
+            .unwrap();
+
+        let expected_body = format!("response={}&secret={}", token, secret);
+
+        let response_template = ResponseTemplate::new(200).set_body_json(json!({
+            "success": true,
+            "hostname": "test-host",
+        }));
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .and(body_string(&expected_body))
+            .respond_with(response_template)
+            .mount(&mock_server)
+            .await;
+        let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+
+        let client = Client::new_with(&uri)
+            .unwrap()
+            .set_remoteip_privacy(RemoteIpPrivacy::Omit);
+        let response = client.verify_request(request).await;
+        assert_ok!(&response);
+        #[cfg(feature = "trace")]
+        assert!(logs_contain("remoteip_privacy=Omit"));
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    #[cfg_attr(feature = "trace", traced_test)]
+    async fn hcaptcha_mock_verify_captcha_with_secret_provider() {
+        let token = random_string(100);
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+        let captcha = Captcha::new(&token).unwrap();
+
+        let expected_body = format!("response={}&secret={}", token, secret);
+
+        let response_template = ResponseTemplate::new(200).set_body_json(json!({
+            "success": true,
+            "hostname": "test-host",
+        }));
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .and(body_string(&expected_body))
+            .respond_with(response_template)
+            .mount(&mock_server)
+            .await;
+        let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+
+        let client = Client::new_with(&uri)
+            .unwrap()
+            .set_secret_provider(secret.parse::<crate::Secret>().unwrap());
+        let response = client.verify_captcha(captcha).await;
+        assert_ok!(&response);
+        assert!(response.unwrap().success());
+    }
+
+    async fn mount_secret_rotation(
+        mock_server: &MockServer,
+        token: &str,
+        secret: &str,
+        error_code: &str,
+    ) {
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .and(body_string(format!("response={token}&secret={secret}")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "success": false,
+                "error-codes": [error_code],
+            })))
+            .expect(1)
+            .mount(mock_server)
+            .await;
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    #[cfg_attr(feature = "trace", traced_test)]
+    async fn hcaptcha_mock_retries_with_fallback_secret() {
+        let token = random_string(100);
+        let primary = format!("0x{}", hex::encode(random_string(20)));
+        let fallback = format!("0x{}", hex::encode(random_string(20)));
+
+        let mock_server = MockServer::start().await;
+        mount_secret_rotation(&mock_server, &token, &primary, "sitekey-secret-mismatch").await;
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .and(body_string(format!("response={token}&secret={fallback}")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "success": true,
+                "hostname": "test-host",
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+
+        let client = Client::new_with(&uri)
+            .unwrap()
+            .set_fallback_secret(fallback.parse().unwrap());
+        let request = Request::new(&primary, Captcha::new(&token).unwrap()).unwrap();
+        let response = client.verify_request(request).await;
+        assert_ok!(&response);
+        assert_eq!(client.fallback_secret_uses(), 1);
+        #[cfg(feature = "trace")]
+        assert!(logs_contain("Request verified with the fallback secret"));
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn hcaptcha_mock_fallback_secret_not_used_for_other_errors() {
+        let token = random_string(100);
+        let primary = format!("0x{}", hex::encode(random_string(20)));
+        let fallback = format!("0x{}", hex::encode(random_string(20)));
+
+        let mock_server = MockServer::start().await;
+        mount_secret_rotation(&mock_server, &token, &primary, "invalid-input-response").await;
+        let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+
+        let client = Client::new_with(&uri)
+            .unwrap()
+            .set_fallback_secret(fallback.parse().unwrap());
+        let request = Request::new(&primary, Captcha::new(&token).unwrap()).unwrap();
+        let response = client.verify_request(request).await;
+        if let Err(Error::Codes(hs)) = response {
+            assert!(hs.contains(&Code::InvalidResponse));
+        } else {
+            panic!("Expected invalid input response error");
+        }
+        assert_eq!(client.fallback_secret_uses(), 0);
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn hcaptcha_mock_fallback_secret_rejected_returns_error() {
+        let token = random_string(100);
+        let primary = format!("0x{}", hex::encode(random_string(20)));
+        let fallback = format!("0x{}", hex::encode(random_string(20)));
+
+        let mock_server = MockServer::start().await;
+        mount_secret_rotation(&mock_server, &token, &primary, "invalid-input-secret").await;
+        mount_secret_rotation(&mock_server, &token, &fallback, "invalid-input-secret").await;
+        let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+
+        let client = Client::new_with(&uri)
+            .unwrap()
+            .set_fallback_secret(fallback.parse().unwrap());
+        let request = Request::new(&primary, Captcha::new(&token).unwrap()).unwrap();
+        let response = client.verify_request(request).await;
+        if let Err(Error::Codes(hs)) = response {
+            assert!(hs.contains(&Code::InvalidSecret));
+        } else {
+            panic!("Expected invalid secret error");
+        }
+        assert_eq!(client.fallback_secret_uses(), 0);
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn hcaptcha_mock_verify_captcha_with_site_registry() {
+        let token = random_string(100);
+        let sitekey = mockd::unique::uuid_v4();
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+        let captcha = Captcha::new(&token).unwrap().set_sitekey(&sitekey).unwrap();
+
+        let expected_body = format!("response={token}&sitekey={sitekey}&secret={secret}");
+
+        let response_template = ResponseTemplate::new(200).set_body_json(json!({
+            "success": true,
+            "hostname": "test-host",
+        }));
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .and(body_string(&expected_body))
+            .respond_with(response_template)
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+        let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+
+        let registry = Arc::new(
+            crate::SiteRegistry::new().add_site(
+                sitekey.parse().unwrap(),
+                crate::Site::new(secret.parse().unwrap())
+                    .set_policy(crate::Policy::new().add_hostname("test-host")),
+            ),
+        );
+        let client = Client::new_with(&uri)
+            .unwrap()
+            .set_site_registry(registry.clone());
+        assert_ok!(client.verify_captcha(captcha.clone()).await);
+
+        let registry = crate::SiteRegistry::new().add_site(
+            sitekey.parse().unwrap(),
+            crate::Site::new(secret.parse().unwrap())
+                .set_policy(crate::Policy::new().add_hostname("other-host")),
+        );
+        let client = Client::new_with(&uri).unwrap().set_site_registry(registry);
+        let response = client.verify_captcha(captcha).await;
+        if let Err(Error::Codes(hs)) = response {
+            assert!(hs.contains(&Code::HostnameMismatch));
+        } else {
+            panic!("Expected hostname mismatch error");
+        }
+    }
+
+    #[tokio::test]
+    async fn verify_captcha_with_unregistered_sitekey_fails() {
+        let captcha = Captcha::new(&random_string(100))
+            .unwrap()
+            .set_sitekey(&mockd::unique::uuid_v4())
+            .unwrap();
+
+        let client = Client::new().set_site_registry(crate::SiteRegistry::new());
+        let response = client.verify_captcha(captcha).await;
+        if let Err(Error::Codes(hs)) = response {
+            assert!(hs.contains(&Code::InvalidSiteKey));
+        } else {
+            panic!("Expected invalid sitekey error");
+        }
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn hcaptcha_mock_token_store_rejects_replayed_token() {
+        let token = random_string(100);
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+
+        let response_template = ResponseTemplate::new(200).set_body_json(json!({
+            "success": true,
+            "hostname": "test-host",
+        }));
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .respond_with(response_template)
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+
+        let client = Client::new_with(&uri)
+            .unwrap()
+            .set_token_store(crate::MemoryTokenStore::new(
+                std::time::Duration::from_secs(60),
+            ));
+
+        let request = Request::new(&secret, Captcha::new(&token).unwrap()).unwrap();
+        assert_ok!(client.verify_request(request).await);
+
+        let request = Request::new(&secret, Captcha::new(&token).unwrap()).unwrap();
+        let response = client.verify_request(request).await;
+        if let Err(Error::Codes(hs)) = response {
+            assert!(hs.contains(&Code::InvalidAlreadySeen));
+        } else {
+            panic!("Expected already seen error");
+        }
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn hcaptcha_mock_token_store_rejects_concurrent_verification() {
+        let token = random_string(100);
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+
+        let response_template = ResponseTemplate::new(200)
+            .set_body_json(json!({ "success": true }))
+            .set_delay(std::time::Duration::from_millis(100));
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .respond_with(response_template)
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+
+        let client = Client::new_with(&uri)
+            .unwrap()
+            .set_token_store(crate::MemoryTokenStore::new(
+                std::time::Duration::from_secs(60),
+            ));
+        let request = || Request::new(&secret, Captcha::new(&token).unwrap()).unwrap();
+
+        let (first, second) = tokio::join!(
+            client.verify_request(request()),
+            client.verify_request(request())
+        );
+
+        let (verified, rejected) = if first.is_ok() {
+            (first, second)
+        } else {
+            (second, first)
+        };
+        assert_ok!(verified);
+        match rejected {
+            Err(Error::Codes(codes)) => assert!(codes.contains(&Code::InvalidAlreadySeen)),
+            _ => panic!("Expected already seen error"),
+        }
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn hcaptcha_mock_token_store_releases_token_on_server_error() {
+        let token = random_string(100);
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "success": true })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+
+        let client = Client::new_with(&uri)
+            .unwrap()
+            .set_token_store(crate::MemoryTokenStore::new(
+                std::time::Duration::from_secs(60),
+            ));
+
+        let request = Request::new(&secret, Captcha::new(&token).unwrap()).unwrap();
+        assert!(matches!(
+            client.verify_request(request).await,
+            Err(Error::Status(503))
+        ));
+        let request = Request::new(&secret, Captcha::new(&token).unwrap()).unwrap();
+        assert_ok!(client.verify_request(request).await);
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn hcaptcha_mock_token_store_does_not_record_failed_token() {
+        let token = random_string(100);
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+
+        let response_template = ResponseTemplate::new(200).set_body_json(json!({
+            "success": false,
+            "error-codes": ["invalid-input-response"],
+        }));
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .respond_with(response_template)
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+        let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+
+        let client = Client::new_with(&uri)
+            .unwrap()
+            .set_token_store(crate::MemoryTokenStore::new(
+                std::time::Duration::from_secs(60),
+            ));
+
+        for _ in 0..2 {
+            let request = Request::new(&secret, Captcha::new(&token).unwrap()).unwrap();
+            assert_err!(client.verify_request(request).await);
+        }
+    }
+
+    #[cfg(feature = "verification-cache")]
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn hcaptcha_mock_verification_cache_issues_pass() {
+        let token = random_string(100);
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+
+        let response_template = ResponseTemplate::new(200).set_body_json(json!({
+            "success": true,
+            "hostname": "test-host",
+        }));
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .respond_with(response_template)
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+
+        let client =
+            Client::new_with(&uri)
+                .unwrap()
+                .set_verification_cache(crate::VerificationCache::new(
+                    b"0123456789abcdef0123456789abcdef",
+                    std::time::Duration::from_secs(600),
+                ));
+
+        let captcha = Captcha::new(&token)
+            .unwrap()
+            .set_remoteip("192.0.2.10")
+            .unwrap();
+        let request = Request::new(&secret, captcha).unwrap();
+        let response = client.verify_request(request).await.unwrap();
+        let pass = response.pass().unwrap();
+
+        let verified = assert_ok!(client.verify_pass(&pass, Some("192.0.2.10".parse().unwrap())));
+        assert_eq!(verified.hostname(), Some("test-host"));
+        assert_err!(Client::new().verify_pass(&pass, Some("192.0.2.10".parse().unwrap())));
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn hcaptcha_mock_rate_limited_request_is_not_sent() {
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+
+        let response_template = ResponseTemplate::new(200).set_body_json(json!({
+            "success": true,
+            "hostname": "test-host",
+        }));
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .respond_with(response_template)
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+        let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+
+        let client = Client::new_with(&uri)
+            .unwrap()
+            .set_rate_limiter(crate::RateLimiter::new().set_per_ip(crate::Quota::per_minute(1)));
+        let request = |ip: &str| {
+            let captcha = Captcha::new(&random_string(100))
+                .unwrap()
+                .set_remoteip(ip)
+                .unwrap();
+            Request::new(&secret, captcha).unwrap()
+        };
+
+        assert_ok!(client.verify_request(request("192.0.2.1")).await);
+        let response = client.verify_request(request("192.0.2.1")).await;
+        assert!(matches!(response, Err(Error::RateLimited)));
+        assert_ok!(client.verify_request(request("192.0.2.2")).await);
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn hcaptcha_mock_concurrency_limit_rejects_queued_request() {
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+
+        let response_template = ResponseTemplate::new(200)
+            .set_body_json(json!({
+                "success": true,
+                "hostname": "test-host",
+            }))
+            .set_delay(std::time::Duration::from_millis(500));
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .respond_with(response_template)
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+
+        let client = Client::new_with(&uri)
+            .unwrap()
+            .set_concurrency_limit(1, std::time::Duration::from_millis(50));
+        let request = || {
+            let captcha = Captcha::new(&random_string(100)).unwrap();
+            Request::new(&secret, captcha).unwrap()
+        };
+
+        let (first, second) = tokio::join!(
+            client.verify_request(request()),
+            client.verify_request(request())
+        );
+        assert_ok!(first);
+        assert!(matches!(second, Err(Error::Overloaded)));
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn hcaptcha_mock_circuit_opens_after_server_errors() {
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+        let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+
+        let client =
+            Client::new_with(&uri)
+                .unwrap()
+                .set_circuit_breaker(crate::CircuitBreaker::new(
+                    2,
+                    std::time::Duration::from_secs(60),
+                ));
+        let request = || {
+            let captcha = Captcha::new(&random_string(100)).unwrap();
+            Request::new(&secret, captcha).unwrap()
+        };
+
+        assert!(matches!(
+            client.verify_request(request()).await,
+            Err(Error::Status(503))
+        ));
+        assert_eq!(client.circuit_state(), Some(crate::CircuitState::Closed));
+        assert!(matches!(
+            client.verify_request(request()).await,
+            Err(Error::Status(503))
+        ));
+        assert_eq!(client.circuit_state(), Some(crate::CircuitState::Open));
+        assert!(matches!(
+            client.verify_request(request()).await,
+            Err(Error::CircuitOpen)
+        ));
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn hcaptcha_mock_rejected_token_does_not_open_circuit() {
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+
+        let response_template = ResponseTemplate::new(200).set_body_json(json!({
+            "success": false,
+            "error-codes": ["invalid-input-response"],
+        }));
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .respond_with(response_template)
+            .mount(&mock_server)
+            .await;
+        let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+
+        let client =
+            Client::new_with(&uri)
+                .unwrap()
+                .set_circuit_breaker(crate::CircuitBreaker::new(
+                    1,
+                    std::time::Duration::from_secs(60),
+                ));
+        let captcha = Captcha::new(&random_string(100)).unwrap();
+        let request = Request::new(&secret, captcha).unwrap();
+
+        assert!(matches!(
+            client.verify_request(request).await,
+            Err(Error::Codes(_))
+        ));
+        assert_eq!(client.circuit_state(), Some(crate::CircuitState::Closed));
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn hcaptcha_mock_not_found_page_does_not_open_circuit() {
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+
+        let response_template =
+            ResponseTemplate::new(404).set_body_raw("<html>Not Found</html>", "text/html");
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .respond_with(response_template)
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+        let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+
+        let client =
+            Client::new_with(&uri)
+                .unwrap()
+                .set_circuit_breaker(crate::CircuitBreaker::new(
+                    1,
+                    std::time::Duration::from_secs(60),
+                ));
+        for _ in 0..2 {
+            let captcha = Captcha::new(&random_string(100)).unwrap();
+            let request = Request::new(&secret, captcha).unwrap();
+            assert!(matches!(
+                client.verify_request(request).await,
+                Err(Error::Status(404))
+            ));
+            assert_eq!(client.circuit_state(), Some(crate::CircuitState::Closed));
+        }
+    }
+
+    async fn mock_endpoint(template: ResponseTemplate, expected: u64) -> (MockServer, String) {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .respond_with(template)
+            .expect(expected)
+            .mount(&mock_server)
+            .await;
+        let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+        (mock_server, uri)
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn hcaptcha_mock_failover_on_server_error() {
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+        let success = ResponseTemplate::new(200).set_body_json(json!({ "success": true }));
+
+        let (_failing, failing_uri) = mock_endpoint(ResponseTemplate::new(503), 1).await;
+        let (_working, working_uri) = mock_endpoint(success.clone(), 1).await;
+        let (_unused, unused_uri) = mock_endpoint(success, 0).await;
+
+        let client = Client::new_with(&failing_uri)
+            .unwrap()
+            .add_failover_url(&working_uri)
+            .unwrap()
+            .add_failover_url(&unused_uri)
+            .unwrap();
+        let captcha = Captcha::new(&random_string(100)).unwrap();
+        let request = Request::new(&secret, captcha).unwrap();
+
+        assert_ok!(client.verify_request(request).await);
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn hcaptcha_mock_failover_on_unreachable_endpoint() {
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+        // Nothing listens on the port once the listener is dropped.
+        let unreachable_uri = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            format!("http://{}/siteverify", listener.local_addr().unwrap())
+        };
+        let success = ResponseTemplate::new(200).set_body_json(json!({ "success": true }));
+        let (_working, working_uri) = mock_endpoint(success, 1).await;
+
+        let client = Client::new_with(&unreachable_uri)
+            .unwrap()
+            .add_failover_url(&working_uri)
+            .unwrap();
+        let captcha = Captcha::new(&random_string(100)).unwrap();
+        let request = Request::new(&secret, captcha).unwrap();
+
+        assert_ok!(client.verify_request(request).await);
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn hcaptcha_mock_no_failover_on_rejected_token() {
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+        let rejected = ResponseTemplate::new(200).set_body_json(json!({
+            "success": false,
+            "error-codes": ["invalid-input-response"],
+        }));
+        let success = ResponseTemplate::new(200).set_body_json(json!({ "success": true }));
+        let (_rejecting, rejecting_uri) = mock_endpoint(rejected, 1).await;
+        let (_unused, unused_uri) = mock_endpoint(success, 0).await;
+
+        let client = Client::new_with(&rejecting_uri)
+            .unwrap()
+            .add_failover_url(&unused_uri)
+            .unwrap();
+        let captcha = Captcha::new(&random_string(100)).unwrap();
+        let request = Request::new(&secret, captcha).unwrap();
+
+        assert!(matches!(
+            client.verify_request(request).await,
+            Err(Error::Codes(_))
+        ));
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn hcaptcha_mock_no_failover_on_not_found_page() {
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+        let not_found =
+            ResponseTemplate::new(404).set_body_raw("<html>Not Found</html>", "text/html");
+        let success = ResponseTemplate::new(200).set_body_json(json!({ "success": true }));
+        let (_misrouted, misrouted_uri) = mock_endpoint(not_found, 1).await;
+        let (_unused, unused_uri) = mock_endpoint(success, 0).await;
+
+        let client = Client::new_with(&misrouted_uri)
+            .unwrap()
+            .add_failover_url(&unused_uri)
+            .unwrap();
+        let captcha = Captcha::new(&random_string(100)).unwrap();
+        let request = Request::new(&secret, captcha).unwrap();
+
+        assert!(matches!(
+            client.verify_request(request).await,
+            Err(Error::Status(404))
+        ));
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn hcaptcha_mock_failover_on_too_many_requests() {
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+        let success = ResponseTemplate::new(200).set_body_json(json!({ "success": true }));
+        let (_limited, limited_uri) = mock_endpoint(ResponseTemplate::new(429), 1).await;
+        let (_working, working_uri) = mock_endpoint(success, 1).await;
+
+        let client = Client::new_with(&limited_uri)
+            .unwrap()
+            .add_failover_url(&working_uri)
+            .unwrap();
+        let captcha = Captcha::new(&random_string(100)).unwrap();
+        let request = Request::new(&secret, captcha).unwrap();
+
+        assert_ok!(client.verify_request(request).await);
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn hcaptcha_mock_all_endpoints_fail() {
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+        let (_first, first_uri) = mock_endpoint(ResponseTemplate::new(503), 1).await;
+        let (_second, second_uri) = mock_endpoint(ResponseTemplate::new(502), 1).await;
+
+        let client = Client::new_with(&first_uri)
+            .unwrap()
+            .add_failover_url(&second_uri)
+            .unwrap();
+        let captcha = Captcha::new(&random_string(100)).unwrap();
+        let request = Request::new(&secret, captcha).unwrap();
+
+        match client.verify_request(request).await {
+            Err(Error::Status(status)) => assert_eq!(status, 502),
+            _ => panic!("Expected the error from the last endpoint"),
+        }
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn hcaptcha_mock_lowest_latency_endpoint_is_preferred() {
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+        let success = ResponseTemplate::new(200).set_body_json(json!({ "success": true }));
+        let (_slow, slow_uri) = mock_endpoint(
+            success
+                .clone()
+                .set_delay(std::time::Duration::from_millis(200)),
+            1,
+        )
+        .await;
+        let (_fast, fast_uri) = mock_endpoint(success, 2).await;
+
+        let client = Client::new_with(&slow_uri)
+            .unwrap()
+            .add_failover_url(&fast_uri)
+            .unwrap()
+            .set_endpoint_selection(EndpointSelection::LowestLatency);
+
+        // Each endpoint is used once to measure it, then the fastest is used.
+        for _ in 0..3 {
+            let captcha = Captcha::new(&random_string(100)).unwrap();
+            let request = Request::new(&secret, captcha).unwrap();
+            assert_ok!(client.verify_request(request).await);
+        }
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn hcaptcha_mock_hedge_on_slow_connection() {
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+        // Accepts connections but never completes the TLS handshake.
+        let stalled = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let stalled_uri = format!("https://{}/siteverify", stalled.local_addr().unwrap());
+        let success = ResponseTemplate::new(200).set_body_json(json!({ "success": true }));
+        let (_hedge, hedge_uri) = mock_endpoint(success, 1).await;
+
+        let client = Client::new_with(&stalled_uri)
+            .unwrap()
+            .add_failover_url(&hedge_uri)
+            .unwrap()
+            .set_hedge_delay(std::time::Duration::from_millis(100));
+        let captcha = Captcha::new(&random_string(100)).unwrap();
+        let request = Request::new(&secret, captcha).unwrap();
+
+        let verification = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            client.verify_request(request),
+        );
+        assert_ok!(verification.await.unwrap());
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn hcaptcha_mock_no_hedge_on_slow_response() {
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+        let success = ResponseTemplate::new(200).set_body_json(json!({ "success": true }));
+        let (_slow, slow_uri) = mock_endpoint(
+            success
+                .clone()
+                .set_delay(std::time::Duration::from_millis(300)),
+            1,
+        )
+        .await;
+        let (_hedge, hedge_uri) = mock_endpoint(success, 0).await;
+
+        let client = Client::new_with(&slow_uri)
+            .unwrap()
+            .add_failover_url(&hedge_uri)
+            .unwrap()
+            .set_hedge_delay(std::time::Duration::from_millis(50));
+        let captcha = Captcha::new(&random_string(100)).unwrap();
+        let request = Request::new(&secret, captcha).unwrap();
+
+        // The connection is made at once, so the token is only sent once.
+        assert_ok!(client.verify_request(request).await);
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn hcaptcha_mock_no_repost_to_hedge_endpoint() {
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+        // Accepts connections but never completes the TLS handshake.
+        let stalled = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let stalled_uri = format!("https://{}/siteverify", stalled.local_addr().unwrap());
+        let success = ResponseTemplate::new(200).set_body_json(json!({ "success": true }));
+        let (_hedge, hedge_uri) = mock_endpoint(ResponseTemplate::new(503), 1).await;
+        let (_working, working_uri) = mock_endpoint(success, 1).await;
+
+        let client = Client::new_with(&stalled_uri)
+            .unwrap()
+            .add_failover_url(&hedge_uri)
+            .unwrap()
+            .add_failover_url(&working_uri)
+            .unwrap()
+            .set_hedge_delay(std::time::Duration::from_millis(100));
+        let captcha = Captcha::new(&random_string(100)).unwrap();
+        let request = Request::new(&secret, captcha).unwrap();
+
+        let verification = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            client.verify_request(request),
+        );
+        assert_ok!(verification.await.unwrap());
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn hcaptcha_mock_no_hedge_on_prompt_response() {
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+        let success = ResponseTemplate::new(200).set_body_json(json!({ "success": true }));
+        let (_prompt, prompt_uri) = mock_endpoint(success.clone(), 1).await;
+        let (_unused, unused_uri) = mock_endpoint(success, 0).await;
+
+        let client = Client::new_with(&prompt_uri)
+            .unwrap()
+            .add_failover_url(&unused_uri)
+            .unwrap()
+            .set_hedge_delay(std::time::Duration::from_secs(5));
+        let captcha = Captcha::new(&random_string(100)).unwrap();
+        let request = Request::new(&secret, captcha).unwrap();
+
+        assert_ok!(client.verify_request(request).await);
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn hcaptcha_mock_failure_mode_on_server_error() {
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&mock_server)
+            .await;
+        let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+        let request = || {
+            let captcha = Captcha::new(&random_string(100)).unwrap();
+            Request::new(&secret, captcha).unwrap()
+        };
+
+        let client = Client::new_with(&uri)
+            .unwrap()
+            .set_failure_mode(FailureMode::FailOpen);
+        let response = client.verify_request(request()).await.unwrap();
+        assert!(response.success());
+        assert!(response.degraded());
+
+        let response = client
+            .verify_request(request().set_failure_mode(FailureMode::FailClosed))
+            .await;
+        assert!(matches!(response, Err(Error::Status(503))));
+
+        let client = Client::new_with(&uri).unwrap();
+        assert_err!(client.verify_request(request()).await);
+        let response = client
+            .verify_request(request().set_failure_mode(FailureMode::FailOpen))
+            .await;
+        assert!(response.unwrap().degraded());
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn hcaptcha_mock_fail_open_does_not_mask_not_found_page() {
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+
+        let response_template =
+            ResponseTemplate::new(404).set_body_raw("<html>Not Found</html>", "text/html");
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .respond_with(response_template)
+            .mount(&mock_server)
+            .await;
+        let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+
+        let client = Client::new_with(&uri)
+            .unwrap()
+            .set_failure_mode(FailureMode::FailOpen);
+        let captcha = Captcha::new(&random_string(100)).unwrap();
+        let response = client
+            .verify_request(Request::new(&secret, captcha).unwrap())
+            .await;
+        assert!(matches!(response, Err(Error::Status(404))));
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn hcaptcha_mock_fail_open_does_not_mask_rejection() {
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+
+        let response_template = ResponseTemplate::new(200).set_body_json(json!({
+            "success": false,
+            "error-codes": ["invalid-input-response"],
+        }));
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .respond_with(response_template)
+            .mount(&mock_server)
+            .await;
+        let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+
+        let client = Client::new_with(&uri)
+            .unwrap()
+            .set_failure_mode(FailureMode::FailOpen);
+        let captcha = Captcha::new(&random_string(100)).unwrap();
+        let response = client
+            .verify_request(Request::new(&secret, captcha).unwrap())
+            .await;
+        assert!(matches!(response, Err(Error::Codes(_))));
+    }
+
+    #[tokio::test]
+    async fn verify_captcha_without_secret_provider_fails() {
+        let captcha = Captcha::new(&random_string(100)).unwrap();
+
+        let response = Client::new().verify_captcha(captcha).await;
+        if let Err(Error::Codes(hs)) = response {
+            assert!(hs.contains(&Code::MissingSecret));
+        } else {
+            panic!("Expected missing secret error");
+        }
+    }
+
+    #[test]
+    fn test_success_response() {
+        let api_response = json!({
+            "success": true,
+            "challenge_ts": "2020-11-11T23:27:00Z",
+            "hostname": "my-host.ie",
+            "credit": true,
+            "error-codes": [],
+            "score": null,
+            "score_reason": [],
+        });
         let response: Response = serde_json::from_value(api_response).unwrap();
         assert!(response.success());
         assert_eq!(
@@ -765,7 +2534,8 @@ mod tests {
         // Here we would check the side effect or state change
         // For example, if new() sets a specific field, we would assert that field's value
         let expected_value = Url::parse(VERIFY_URL).unwrap();
-        assert!(client.url == expected_value);
+        assert!(*client.endpoints.primary() == expected_value);
+        assert_eq!(client.remoteip_privacy, RemoteIpPrivacy::Full);
     }
 
     #[test]
@@ -773,7 +2543,10 @@ mod tests {
         let client = Client::default();
         let result = client.set_url("https://example.com");
         assert!(result.is_ok());
-        assert_eq!(result.unwrap().url.as_str(), "https://example.com/");
+        assert_eq!(
+            result.unwrap().endpoints.primary().as_str(),
+            "https://example.com/"
+        );
     }
 
     #[test]
@@ -797,4 +2570,205 @@ mod tests {
             _ => panic!("Expected UrlParseError"),
         }
     }
+
+    #[derive(Debug, Default)]
+    struct RecordingTransport {
+        forms: std::sync::Mutex<Vec<(String, String)>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Transport for RecordingTransport {
+        async fn post_form(&self, url: &Url, form: String) -> Result<TransportResponse, Error> {
+            self.forms.lock().unwrap().push((url.to_string(), form));
+            Ok(TransportResponse::new(
+                200,
+                json!({ "success": true }).to_string().into_bytes(),
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn verify_request_uses_the_transport() {
+        let transport = Arc::new(RecordingTransport::default());
+        let client = Client::new_with("https://verify.example.com/siteverify")
+            .unwrap()
+            .set_transport(Arc::clone(&transport));
+        let captcha = Captcha::new("response_token").unwrap();
+        let request = Request::new("0x123456789abcde0f123456789abcdef012345678", captcha).unwrap();
+
+        let response = client.verify_request(request).await;
+
+        assert_ok!(&response);
+        let forms = transport.forms.lock().unwrap();
+        assert_eq!(forms.len(), 1);
+        assert_eq!(forms[0].0, "https://verify.example.com/siteverify");
+        assert!(forms[0].1.contains("response=response_token"));
+    }
+
+    #[test]
+    fn hedge_uses_the_connect_timeout_of_the_transport() {
+        /// Fails to connect within the timeout to every url but the hedge url.
+        #[derive(Debug)]
+        struct SlowToConnect {
+            hedge_url: Url,
+            attempts: std::sync::Mutex<Vec<(String, Option<Duration>)>>,
+        }
+
+        #[async_trait::async_trait]
+        impl Transport for SlowToConnect {
+            async fn post_form(
+                &self,
+                url: &Url,
+                _form: String,
+            ) -> Result<TransportResponse, Error> {
+                self.attempts.lock().unwrap().push((url.to_string(), None));
+                Ok(TransportResponse::new(
+                    200,
+                    json!({ "success": true }).to_string().into_bytes(),
+                ))
+            }
+
+            async fn post_form_with_connect_timeout(
+                &self,
+                url: &Url,
+                form: String,
+                connect_timeout: Duration,
+            ) -> Result<TransportResponse, Error> {
+                if *url == self.hedge_url {
+                    return self.post_form(url, form).await;
+                }
+                self.attempts
+                    .lock()
+                    .unwrap()
+                    .push((url.to_string(), Some(connect_timeout)));
+                Err(Error::Connect("connect timed out".into()))
+            }
+        }
+
+        let transport = Arc::new(SlowToConnect {
+            hedge_url: Url::parse("https://hedge.example.com/siteverify").unwrap(),
+            attempts: std::sync::Mutex::default(),
+        });
+        let client = Client::new_with("https://verify.example.com/siteverify")
+            .unwrap()
+            .add_failover_url("https://hedge.example.com/siteverify")
+            .unwrap()
+            .set_transport(Arc::clone(&transport))
+            .set_hedge_delay(Duration::from_millis(10));
+        let captcha = Captcha::new("response_token").unwrap();
+        let request = Request::new("0x123456789abcde0f123456789abcdef012345678", captcha).unwrap();
+
+        let response = futures::executor::block_on(client.verify_request(request));
+
+        assert_ok!(&response);
+        assert_eq!(
+            *transport.attempts.lock().unwrap(),
+            [
+                (
+                    "https://verify.example.com/siteverify".to_owned(),
+                    Some(Duration::from_millis(10))
+                ),
+                ("https://hedge.example.com/siteverify".to_owned(), None),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn server_error_status_is_returned() {
+        #[derive(Debug)]
+        struct Unavailable;
+
+        #[async_trait::async_trait]
+        impl Transport for Unavailable {
+            async fn post_form(
+                &self,
+                _url: &Url,
+                _form: String,
+            ) -> Result<TransportResponse, Error> {
+                Ok(TransportResponse::new(503, Vec::new()))
+            }
+        }
+
+        let client = Client::new().set_transport(Unavailable);
+        let captcha = Captcha::new("response_token").unwrap();
+        let request = Request::new("0x123456789abcde0f123456789abcdef012345678", captcha).unwrap();
+
+        assert!(matches!(
+            client.verify_request(request).await,
+            Err(Error::Status(503))
+        ));
+    }
+
+    #[test]
+    fn verify_request_under_futures_executor() {
+        let client = Client::new()
+            .set_transport(RecordingTransport::default())
+            .set_timer(crate::ThreadTimer)
+            .set_concurrency_limit(1, Duration::from_secs(1));
+        let captcha = Captcha::new("response_token").unwrap();
+        let request = Request::new("0x123456789abcde0f123456789abcdef012345678", captcha).unwrap();
+
+        let response = futures::executor::block_on(client.verify_request(request));
+
+        assert_ok!(&response);
+        assert!(response.unwrap().success());
+    }
+
+    /// Transport whose requests never complete.
+    #[derive(Debug)]
+    struct Hanging;
+
+    #[async_trait::async_trait]
+    impl Transport for Hanging {
+        async fn post_form(&self, _url: &Url, _form: String) -> Result<TransportResponse, Error> {
+            std::future::pending().await
+        }
+    }
+
+    #[test]
+    fn concurrency_limit_times_out_under_smol() {
+        let client = Client::new()
+            .set_transport(Hanging)
+            .set_timer(crate::ThreadTimer)
+            .set_concurrency_limit(1, Duration::from_millis(10));
+        let request = || {
+            let captcha = Captcha::new("response_token").unwrap();
+            Request::new("0x123456789abcde0f123456789abcdef012345678", captcha).unwrap()
+        };
+
+        // The first verification takes the only permit and never completes.
+        let first = std::pin::pin!(client.verify_request(request()));
+        let second = std::pin::pin!(client.verify_request(request()));
+        let second = match smol::block_on(futures::future::select(first, second)) {
+            futures::future::Either::Right((second, _)) => second,
+            futures::future::Either::Left(_) => {
+                panic!("Expected the second verification to complete")
+            }
+        };
+
+        assert!(matches!(second, Err(Error::Overloaded)));
+    }
+
+    #[test]
+    fn concurrency_limit_times_out_with_the_default_timer() {
+        let client = Client::new()
+            .set_transport(Hanging)
+            .set_concurrency_limit(1, Duration::from_millis(10));
+        let request = || {
+            let captcha = Captcha::new("response_token").unwrap();
+            Request::new("0x123456789abcde0f123456789abcdef012345678", captcha).unwrap()
+        };
+
+        // No tokio runtime is running, so the default timer must not need one.
+        let first = std::pin::pin!(client.verify_request(request()));
+        let second = std::pin::pin!(client.verify_request(request()));
+        let second = match futures::executor::block_on(futures::future::select(first, second)) {
+            futures::future::Either::Right((second, _)) => second,
+            futures::future::Either::Left(_) => {
+                panic!("Expected the second verification to complete")
+            }
+        };
+
+        assert!(matches!(second, Err(Error::Overloaded)));
+    }
 }
@@ -0,0 +1,271 @@
+// SPDX-FileCopyrightText: 2022 jerusdp
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// The state of a [CircuitBreaker].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests are sent to the endpoint.
+    Closed,
+    /// Requests fail fast without calling the endpoint until the cooldown
+    /// has passed.
+    Open,
+    /// A limited number of probe requests are sent to the endpoint to test
+    /// if it has recovered.
+    HalfOpen,
+}
+
+#[derive(Debug)]
+struct Inner {
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Instant,
+    probes_in_flight: u32,
+}
+
+/// Fail fast while the Hcaptcha API is unavailable.
+///
+/// The circuit opens after `failure_threshold` consecutive failures to
+/// connect, timeouts, server errors or `429 Too Many Requests` responses
+/// from the endpoint. While the circuit is open
+/// verifications are rejected with
+/// [Error::CircuitOpen](crate::Error::CircuitOpen) without calling the
+/// endpoint. Once the cooldown has passed the circuit half-opens and sends
+/// probe requests; the circuit closes if a probe succeeds and opens again if
+/// it fails.
+///
+/// Responses that reject the token are successful calls to the endpoint and
+/// do not open the circuit, nor do other error statuses such as `404` that
+/// point to a misconfigured endpoint.
+///
+/// # Example
+///
+/// ```
+///     use hcaptcha::{CircuitBreaker, Client};
+///     use std::time::Duration;
+///
+///     let client = Client::new()
+///         .set_circuit_breaker(CircuitBreaker::new(5, Duration::from_secs(30)));
+/// ```
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    /// Consecutive failures that open the circuit.
+    failure_threshold: u32,
+    /// How long the circuit stays open before probing the endpoint.
+    cooldown: Duration,
+    /// Number of probe requests allowed at once while half-open.
+    max_probes: u32,
+    inner: Mutex<Inner>,
+}
+
+impl CircuitBreaker {
+    /// Open the circuit after `failure_threshold` consecutive failures and
+    /// keep it open for `cooldown`.
+    ///
+    /// A `failure_threshold` of zero is treated as one. One probe request
+    /// is allowed at a time while half-open.
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        CircuitBreaker {
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+            max_probes: 1,
+            inner: Mutex::new(Inner {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                opened_at: Instant::now(),
+                probes_in_flight: 0,
+            }),
+        }
+    }
+
+    /// Set the number of probe requests allowed at once while half-open.
+    ///
+    /// A value of zero is treated as one.
+    pub fn set_max_probes(mut self, max_probes: u32) -> Self {
+        self.max_probes = max_probes.max(1);
+        self
+    }
+
+    /// The current state of the circuit.
+    pub fn state(&self) -> CircuitState {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        self.half_open_after_cooldown(&mut inner, Instant::now());
+        inner.state
+    }
+
+    /// Start a call to the endpoint.
+    ///
+    /// Returns `None` if the circuit is open or all probes are in flight.
+    pub(crate) fn attempt(&self) -> Option<Attempt<'_>> {
+        self.attempt_at(Instant::now())
+    }
+
+    fn attempt_at(&self, now: Instant) -> Option<Attempt<'_>> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        self.half_open_after_cooldown(&mut inner, now);
+        match inner.state {
+            CircuitState::Closed => Some(Attempt {
+                breaker: self,
+                probe: false,
+            }),
+            CircuitState::HalfOpen if inner.probes_in_flight < self.max_probes => {
+                inner.probes_in_flight += 1;
+                Some(Attempt {
+                    breaker: self,
+                    probe: true,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn half_open_after_cooldown(&self, inner: &mut Inner, now: Instant) {
+        if inner.state == CircuitState::Open
+            && now.saturating_duration_since(inner.opened_at) >= self.cooldown
+        {
+            #[cfg(feature = "trace")]
+            tracing::debug!("Circuit half-open");
+            inner.state = CircuitState::HalfOpen;
+            inner.probes_in_flight = 0;
+        }
+    }
+
+    fn record(&self, probe: bool, success: bool, now: Instant) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if probe {
+            inner.probes_in_flight = inner.probes_in_flight.saturating_sub(1);
+        }
+        if success {
+            if inner.state != CircuitState::Closed {
+                #[cfg(feature = "trace")]
+                tracing::info!("Circuit closed");
+            }
+            inner.state = CircuitState::Closed;
+            inner.consecutive_failures = 0;
+            return;
+        }
+
+        inner.consecutive_failures = inner.consecutive_failures.saturating_add(1);
+        let reopen = probe && inner.state == CircuitState::HalfOpen;
+        if reopen
+            || (inner.state == CircuitState::Closed
+                && inner.consecutive_failures >= self.failure_threshold)
+        {
+            #[cfg(feature = "trace")]
+            tracing::warn!(
+                consecutive_failures = inner.consecutive_failures,
+                "Circuit opened"
+            );
+            inner.state = CircuitState::Open;
+            inner.opened_at = now;
+        }
+    }
+}
+
+/// A call to the endpoint allowed by the [CircuitBreaker].
+///
+/// A probe that is dropped without recording the outcome releases its
+/// place so that another probe can be sent.
+#[derive(Debug)]
+pub(crate) struct Attempt<'a> {
+    breaker: &'a CircuitBreaker,
+    probe: bool,
+}
+
+impl Attempt<'_> {
+    /// Record the outcome of the call.
+    pub(crate) fn record(self, success: bool) {
+        self.breaker.record(self.probe, success, Instant::now());
+        std::mem::forget(self);
+    }
+}
+
+impl Drop for Attempt<'_> {
+    fn drop(&mut self) {
+        if self.probe {
+            let mut inner = self.breaker.inner.lock().unwrap_or_else(|e| e.into_inner());
+            inner.probes_in_flight = inner.probes_in_flight.saturating_sub(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::{assert_none, assert_some};
+
+    const COOLDOWN: Duration = Duration::from_secs(30);
+
+    fn fail(breaker: &CircuitBreaker, now: Instant) {
+        let attempt = assert_some!(breaker.attempt_at(now));
+        breaker.record(attempt.probe, false, now);
+        std::mem::forget(attempt);
+    }
+
+    #[test]
+    fn circuit_opens_after_consecutive_failures() {
+        let breaker = CircuitBreaker::new(3, COOLDOWN);
+        let now = Instant::now();
+
+        fail(&breaker, now);
+        fail(&breaker, now);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        fail(&breaker, now);
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert_none!(breaker.attempt_at(now));
+    }
+
+    #[test]
+    fn success_resets_consecutive_failures() {
+        let breaker = CircuitBreaker::new(2, COOLDOWN);
+        let now = Instant::now();
+
+        fail(&breaker, now);
+        breaker.attempt_at(now).unwrap().record(true);
+        fail(&breaker, now);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn circuit_half_opens_after_cooldown_and_closes_on_success() {
+        let breaker = CircuitBreaker::new(1, COOLDOWN);
+        let now = Instant::now();
+        fail(&breaker, now);
+
+        let later = now + COOLDOWN;
+        let probe = assert_some!(breaker.attempt_at(later));
+        assert!(probe.probe);
+        assert_none!(breaker.attempt_at(later));
+
+        probe.record(true);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn failed_probe_opens_circuit() {
+        let breaker = CircuitBreaker::new(3, COOLDOWN);
+        let now = Instant::now();
+        for _ in 0..3 {
+            fail(&breaker, now);
+        }
+
+        let later = now + COOLDOWN;
+        fail(&breaker, later);
+        assert_none!(breaker.attempt_at(later + Duration::from_secs(1)));
+        assert_some!(breaker.attempt_at(later + COOLDOWN));
+    }
+
+    #[test]
+    fn dropped_probe_releases_its_place() {
+        let breaker = CircuitBreaker::new(1, COOLDOWN).set_max_probes(1);
+        let now = Instant::now();
+        fail(&breaker, now);
+
+        let later = now + COOLDOWN;
+        drop(assert_some!(breaker.attempt_at(later)));
+        assert_some!(breaker.attempt_at(later));
+    }
+}
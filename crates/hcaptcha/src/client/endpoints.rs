@@ -0,0 +1,202 @@
+// SPDX-FileCopyrightText: 2022 jerusdp
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::Error;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::Instant;
+use url::Url;
+
+/// Latency recorded for an endpoint that failed to respond.
+const FAILURE_LATENCY: Duration = Duration::from_secs(10);
+/// Weight of the latest measurement in the average latency.
+const LATENCY_WEIGHT: f64 = 0.2;
+
+/// Control the order in which the endpoints are tried.
+///
+/// # Example
+///
+/// ```
+/// # fn main() -> Result<(), hcaptcha::Error> {
+///     use hcaptcha::{Client, EndpointSelection};
+///
+///     let client = Client::new_with("https://api.hcaptcha.com/siteverify")?
+///         .add_failover_url("https://hcaptcha.com/siteverify")?
+///         .set_endpoint_selection(EndpointSelection::LowestLatency);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum EndpointSelection {
+    /// Try the endpoints in the order they were added (default).
+    #[default]
+    Ordered,
+    /// Try the endpoint with the lowest average latency first. Endpoints
+    /// that have not been used are tried first, in the order they were
+    /// added. Latency is not measured on `wasm32-unknown-unknown`, where
+    /// the endpoints are tried in order.
+    LowestLatency,
+}
+
+/// A siteverify endpoint and its average latency.
+#[derive(Debug)]
+pub(crate) struct Endpoint {
+    url: Url,
+    latency: Mutex<Option<Duration>>,
+}
+
+impl Endpoint {
+    fn new(url: Url) -> Self {
+        Endpoint {
+            url,
+            latency: Mutex::new(None),
+        }
+    }
+
+    pub(crate) fn url(&self) -> &Url {
+        &self.url
+    }
+
+    fn latency(&self) -> Option<Duration> {
+        *self.latency.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), allow(dead_code))]
+    fn record_latency(&self, elapsed: Option<Duration>) {
+        let mut latency = self.latency.lock().unwrap_or_else(|e| e.into_inner());
+        *latency = match (elapsed, *latency) {
+            (None, _) => Some(FAILURE_LATENCY),
+            (Some(elapsed), Some(average)) if average < FAILURE_LATENCY => {
+                Some(average.mul_f64(1.0 - LATENCY_WEIGHT) + elapsed.mul_f64(LATENCY_WEIGHT))
+            }
+            (Some(elapsed), _) => Some(elapsed),
+        };
+    }
+
+    /// Run the call to the endpoint and record its latency.
+    ///
+    /// A call that fails because the endpoint is unavailable is recorded as
+    /// slow so that the endpoint is tried last.
+    pub(crate) async fn timed<T>(
+        &self,
+        call: impl Future<Output = Result<T, Error>>,
+    ) -> Result<T, Error> {
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        let start = Instant::now();
+        let result = call.await;
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        match &result {
+            Err(e) if super::is_transient(e) => self.record_latency(None),
+            _ => self.record_latency(Some(start.elapsed())),
+        }
+        result
+    }
+}
+
+/// The ordered list of siteverify endpoints.
+#[derive(Debug)]
+pub(crate) struct Endpoints {
+    endpoints: Vec<Endpoint>,
+    selection: EndpointSelection,
+}
+
+impl Endpoints {
+    pub(crate) fn new(url: Url) -> Self {
+        Endpoints {
+            endpoints: vec![Endpoint::new(url)],
+            selection: EndpointSelection::default(),
+        }
+    }
+
+    /// The first endpoint.
+    pub(crate) fn primary(&self) -> &Url {
+        &self.endpoints[0].url
+    }
+
+    /// Replace the first endpoint.
+    pub(crate) fn set_primary(&mut self, url: Url) {
+        self.endpoints[0] = Endpoint::new(url);
+    }
+
+    /// Add an endpoint to try if the earlier endpoints fail.
+    pub(crate) fn push(&mut self, url: Url) {
+        self.endpoints.push(Endpoint::new(url));
+    }
+
+    pub(crate) fn set_selection(&mut self, selection: EndpointSelection) {
+        self.selection = selection;
+    }
+
+    /// The endpoints in the order to try them.
+    pub(crate) fn ordered(&self) -> Vec<&Endpoint> {
+        let mut endpoints: Vec<&Endpoint> = self.endpoints.iter().collect();
+        if self.selection == EndpointSelection::LowestLatency {
+            endpoints.sort_by_key(|endpoint| endpoint.latency());
+        }
+        endpoints
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoints() -> Endpoints {
+        let mut endpoints = Endpoints::new(Url::parse("https://one.example/").unwrap());
+        endpoints.push(Url::parse("https://two.example/").unwrap());
+        endpoints.push(Url::parse("https://three.example/").unwrap());
+        endpoints
+    }
+
+    fn hosts(endpoints: &Endpoints) -> Vec<&str> {
+        endpoints
+            .ordered()
+            .iter()
+            .map(|e| e.url().host_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn endpoints_are_ordered_as_added() {
+        let endpoints = endpoints();
+        endpoints.endpoints[0].record_latency(Some(Duration::from_millis(500)));
+        assert_eq!(
+            hosts(&endpoints),
+            ["one.example", "two.example", "three.example"]
+        );
+    }
+
+    #[test]
+    fn lowest_latency_tries_unmeasured_then_fastest() {
+        let mut endpoints = endpoints();
+        endpoints.set_selection(EndpointSelection::LowestLatency);
+        endpoints.endpoints[0].record_latency(Some(Duration::from_millis(500)));
+        endpoints.endpoints[2].record_latency(Some(Duration::from_millis(50)));
+        assert_eq!(
+            hosts(&endpoints),
+            ["two.example", "three.example", "one.example"]
+        );
+
+        endpoints.endpoints[1].record_latency(None);
+        assert_eq!(
+            hosts(&endpoints),
+            ["three.example", "one.example", "two.example"]
+        );
+    }
+
+    #[test]
+    fn latency_is_averaged() {
+        let endpoints = endpoints();
+        let endpoint = &endpoints.endpoints[0];
+        endpoint.record_latency(Some(Duration::from_millis(100)));
+        endpoint.record_latency(Some(Duration::from_millis(200)));
+        assert_eq!(endpoint.latency(), Some(Duration::from_millis(120)));
+
+        endpoint.record_latency(None);
+        endpoint.record_latency(Some(Duration::from_millis(100)));
+        assert_eq!(endpoint.latency(), Some(Duration::from_millis(100)));
+    }
+}
@@ -0,0 +1,32 @@
+// SPDX-FileCopyrightText: 2022 jerusdp
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+/// Control the outcome of a verification when the Hcaptcha API cannot be
+/// reached.
+///
+/// The failure mode only applies when the endpoint is unavailable: the
+/// connection failed or timed out, the endpoint answered with a server error
+/// or `429 Too Many Requests`, or the [CircuitBreaker](crate::CircuitBreaker)
+/// is open. A response from the API that rejects the token, any other error
+/// status such as `404` and a body that is not a siteverify response are
+/// always returned as errors.
+///
+/// # Example
+///
+/// ```
+///     use hcaptcha::{Client, FailureMode};
+///
+///     // Keep a low risk form working during an outage of the API.
+///     let client = Client::new()
+///         .set_failure_mode(FailureMode::FailOpen);
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FailureMode {
+    /// Return the error (default).
+    #[default]
+    FailClosed,
+    /// Return a successful [Response](crate::Response) flagged as
+    /// [degraded](crate::Response::degraded).
+    FailOpen,
+}
@@ -2,7 +2,9 @@
 //
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use super::RemoteIpPrivacy;
 use crate::Request;
+use crate::Secret;
 
 #[derive(Debug, Clone, serde::Serialize)]
 pub(crate) struct Form {
@@ -12,9 +14,13 @@ pub(crate) struct Form {
     secret: String,
 }
 
-impl From<Request> for Form {
-    fn from(request: Request) -> Form {
-        let remoteip = request.captcha().remoteip.map(|v| v.to_string());
+impl Form {
+    /// Build the form from the request, applying the privacy mode to the
+    /// remote ip.
+    pub(crate) fn new(request: Request, privacy: RemoteIpPrivacy) -> Form {
+        let remoteip = privacy
+            .apply(request.captcha().remoteip)
+            .map(|v| v.to_string());
         let sitekey = request.captcha().sitekey.map(|v| v.to_string());
 
         Form {
@@ -24,4 +30,15 @@ impl From<Request> for Form {
             secret: request.secret().to_string(),
         }
     }
+
+    /// Replace the secret in the form.
+    pub(crate) fn with_secret(mut self, secret: &Secret) -> Form {
+        self.secret = secret.to_string();
+        self
+    }
+
+    /// Check if the form uses the secret.
+    pub(crate) fn uses_secret(&self, secret: &Secret) -> bool {
+        self.secret == secret.as_str()
+    }
 }
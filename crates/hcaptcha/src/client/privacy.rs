@@ -0,0 +1,99 @@
+// SPDX-FileCopyrightText: 2022 jerusdp
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::domain::Remoteip;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// Number of leading bits of an IPv4 address kept by [RemoteIpPrivacy::Truncate].
+const IPV4_PREFIX_LEN: u32 = 24;
+/// Number of leading bits of an IPv6 address kept by [RemoteIpPrivacy::Truncate].
+const IPV6_PREFIX_LEN: u32 = 48;
+
+/// Control how much of the client's ip address is sent to the Hcaptcha API.
+///
+/// The remote ip is optional for the Hcaptcha API. It can be reduced to the
+/// network prefix, which keeps a coarse location signal, or left out of the
+/// request altogether to minimise the personal data sent to the service.
+///
+/// # Example
+///
+/// ```
+///     use hcaptcha::{Client, RemoteIpPrivacy};
+///
+///     let client = Client::new()
+///         .set_remoteip_privacy(RemoteIpPrivacy::Truncate);
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RemoteIpPrivacy {
+    /// Send the remote ip as supplied (default).
+    #[default]
+    Full,
+    /// Send only the network prefix of the remote ip: the first 24 bits
+    /// of an IPv4 address and the first 48 bits of an IPv6 address.
+    Truncate,
+    /// Do not send the remote ip.
+    Omit,
+}
+
+impl RemoteIpPrivacy {
+    /// Apply the privacy mode to the remote ip.
+    pub(crate) fn apply(&self, remoteip: Option<Remoteip>) -> Option<Remoteip> {
+        match self {
+            RemoteIpPrivacy::Full => remoteip,
+            RemoteIpPrivacy::Truncate => remoteip.map(|ip| truncate(ip.ip()).into()),
+            RemoteIpPrivacy::Omit => None,
+        }
+    }
+}
+
+fn truncate(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V4(ip) => {
+            let mask = u32::MAX << (u32::BITS - IPV4_PREFIX_LEN);
+            IpAddr::V4(Ipv4Addr::from(u32::from(ip) & mask))
+        }
+        IpAddr::V6(ip) => {
+            let mask = u128::MAX << (u128::BITS - IPV6_PREFIX_LEN);
+            IpAddr::V6(Ipv6Addr::from(u128::from(ip) & mask))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::assert_none;
+
+    fn remoteip(s: &str) -> Option<Remoteip> {
+        Some(Remoteip::parse(s.to_owned()).unwrap())
+    }
+
+    #[test]
+    fn full_keeps_remoteip() {
+        let ip = remoteip("192.0.2.123");
+        assert_eq!(RemoteIpPrivacy::Full.apply(ip), ip);
+    }
+
+    #[test]
+    fn truncate_keeps_ipv4_network_prefix() {
+        let ip = RemoteIpPrivacy::Truncate.apply(remoteip("192.0.2.123"));
+        assert_eq!(ip.unwrap().to_string(), "192.0.2.0");
+    }
+
+    #[test]
+    fn truncate_keeps_ipv6_network_prefix() {
+        let ip = RemoteIpPrivacy::Truncate.apply(remoteip("2001:db8:abcd:12:3456::1"));
+        assert_eq!(ip.unwrap().to_string(), "2001:db8:abcd::");
+    }
+
+    #[test]
+    fn omit_removes_remoteip() {
+        assert_none!(RemoteIpPrivacy::Omit.apply(remoteip("192.0.2.123")));
+    }
+
+    #[test]
+    fn missing_remoteip_stays_missing() {
+        assert_none!(RemoteIpPrivacy::Truncate.apply(None));
+    }
+}
@@ -0,0 +1,305 @@
+// SPDX-FileCopyrightText: 2022 jerusdp
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::domain::Remoteip;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Maximum number of remote ips tracked. When it is reached the ips with
+/// full buckets are removed, then the least recently used ips until half
+/// of the maximum remain.
+const MAX_TRACKED_IPS: usize = 10_000;
+
+/// The number of verifications allowed in a period of time.
+///
+/// Up to `burst` verifications are allowed at once and the allowance is
+/// refilled at a steady rate of `burst` verifications per `period`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    /// The number of verifications allowed at once.
+    burst: u32,
+    /// The time to refill the full allowance.
+    period: Duration,
+}
+
+impl Quota {
+    /// Allow `burst` verifications per `period`.
+    ///
+    /// A `burst` of zero is treated as one.
+    pub fn new(burst: u32, period: Duration) -> Self {
+        Quota {
+            burst: burst.max(1),
+            period,
+        }
+    }
+
+    /// Allow `burst` verifications per second.
+    pub fn per_second(burst: u32) -> Self {
+        Quota::new(burst, Duration::from_secs(1))
+    }
+
+    /// Allow `burst` verifications per minute.
+    pub fn per_minute(burst: u32) -> Self {
+        Quota::new(burst, Duration::from_secs(60))
+    }
+}
+
+/// A token bucket for a quota.
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn full(quota: &Quota, now: Instant) -> Self {
+        Bucket {
+            tokens: f64::from(quota.burst),
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, quota: &Quota, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated);
+        let burst = f64::from(quota.burst);
+        let refilled = if quota.period.is_zero() {
+            burst
+        } else {
+            burst * elapsed.as_secs_f64() / quota.period.as_secs_f64()
+        };
+        self.tokens = (self.tokens + refilled).min(burst);
+        self.updated = now;
+    }
+
+    fn is_full(&self, quota: &Quota, now: Instant) -> bool {
+        let mut bucket = *self;
+        bucket.refill(quota, now);
+        bucket.tokens >= f64::from(quota.burst)
+    }
+
+    fn has_token(&mut self, quota: &Quota, now: Instant) -> bool {
+        self.refill(quota, now);
+        self.tokens >= 1.0
+    }
+
+    fn take(&mut self) {
+        self.tokens -= 1.0;
+    }
+}
+
+/// Remove buckets to make room for a new remote ip.
+///
+/// Full buckets carry no state and are removed first. If more than half of
+/// the maximum remain, the least recently used are removed as well so that
+/// the map stays bounded when many distinct ips are seen.
+fn evict(buckets: &mut HashMap<IpAddr, Bucket>, quota: &Quota, now: Instant) {
+    buckets.retain(|_, bucket| !bucket.is_full(quota, now));
+    let keep = MAX_TRACKED_IPS / 2;
+    if buckets.len() <= keep {
+        return;
+    }
+    let mut by_age: Vec<(Instant, IpAddr)> = buckets
+        .iter()
+        .map(|(ip, bucket)| (bucket.updated, *ip))
+        .collect();
+    let remove = by_age.len() - keep;
+    by_age.select_nth_unstable(remove - 1);
+    for (_, ip) in &by_age[..remove] {
+        buckets.remove(ip);
+    }
+}
+
+/// Limit the rate of verifications sent to the Hcaptcha API.
+///
+/// Verifications over the limit are rejected by the [Client](crate::Client)
+/// with [Error::RateLimited](crate::Error::RateLimited) without calling the
+/// API. A global quota limits all verifications and a per ip quota limits
+/// the verifications for each remote ip. Requests without a remote ip are
+/// only limited by the global quota.
+///
+/// # Example
+///
+/// ```
+///     use hcaptcha::{Client, Quota, RateLimiter};
+///
+///     let client = Client::new().set_rate_limiter(
+///         RateLimiter::new()
+///             .set_global(Quota::per_second(100))
+///             .set_per_ip(Quota::per_minute(10)),
+///     );
+/// ```
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    /// Quota and bucket for all verifications.
+    global: Option<(Quota, Mutex<Bucket>)>,
+    /// Quota and buckets for each remote ip.
+    per_ip: Option<(Quota, Mutex<HashMap<IpAddr, Bucket>>)>,
+}
+
+impl RateLimiter {
+    /// Create a rate limiter that allows every verification.
+    pub fn new() -> Self {
+        RateLimiter::default()
+    }
+
+    /// Set the quota for all verifications.
+    pub fn set_global(mut self, quota: Quota) -> Self {
+        self.global = Some((quota, Mutex::new(Bucket::full(&quota, Instant::now()))));
+        self
+    }
+
+    /// Set the quota for the verifications from each remote ip.
+    pub fn set_per_ip(mut self, quota: Quota) -> Self {
+        self.per_ip = Some((quota, Mutex::new(HashMap::new())));
+        self
+    }
+
+    /// Take an allowance for a verification from the remote ip.
+    ///
+    /// Returns false if the verification is over the limit.
+    pub(crate) fn check(&self, remoteip: Option<Remoteip>) -> bool {
+        self.check_at(remoteip, Instant::now())
+    }
+
+    fn check_at(&self, remoteip: Option<Remoteip>, now: Instant) -> bool {
+        // Both quotas are checked before an allowance is taken from either,
+        // so a verification rejected by one does not use up the other.
+        let mut per_ip = match (&self.per_ip, remoteip) {
+            (Some((quota, buckets)), Some(remoteip)) => {
+                let mut buckets = buckets.lock().unwrap_or_else(|e| e.into_inner());
+                if buckets.len() >= MAX_TRACKED_IPS && !buckets.contains_key(&remoteip.ip()) {
+                    evict(&mut buckets, quota, now);
+                }
+                let bucket = buckets
+                    .entry(remoteip.ip())
+                    .or_insert_with(|| Bucket::full(quota, now));
+                if !bucket.has_token(quota, now) {
+                    #[cfg(feature = "trace")]
+                    tracing::debug!("Rate limit exceeded for remote ip {}", remoteip);
+                    return false;
+                }
+                Some((buckets, remoteip.ip()))
+            }
+            _ => None,
+        };
+        if let Some((quota, bucket)) = &self.global {
+            let mut bucket = bucket.lock().unwrap_or_else(|e| e.into_inner());
+            if !bucket.has_token(quota, now) {
+                #[cfg(feature = "trace")]
+                tracing::debug!("Global rate limit exceeded");
+                return false;
+            }
+            bucket.take();
+        }
+        if let Some((buckets, ip)) = &mut per_ip {
+            if let Some(bucket) = buckets.get_mut(ip) {
+                bucket.take();
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> Option<Remoteip> {
+        Some(s.parse().unwrap())
+    }
+
+    #[test]
+    fn no_quota_allows_every_verification() {
+        let limiter = RateLimiter::new();
+        let now = Instant::now();
+        assert!((0..1000).all(|_| limiter.check_at(ip("192.0.2.1"), now)));
+    }
+
+    #[test]
+    fn global_quota_limits_burst_and_refills() {
+        let limiter = RateLimiter::new().set_global(Quota::new(2, Duration::from_secs(10)));
+        let now = Instant::now();
+
+        assert!(limiter.check_at(None, now));
+        assert!(limiter.check_at(ip("192.0.2.1"), now));
+        assert!(!limiter.check_at(ip("192.0.2.2"), now));
+
+        assert!(!limiter.check_at(None, now + Duration::from_secs(4)));
+        assert!(limiter.check_at(None, now + Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn per_ip_quota_limits_each_ip() {
+        let limiter = RateLimiter::new().set_per_ip(Quota::new(1, Duration::from_secs(60)));
+        let now = Instant::now();
+
+        assert!(limiter.check_at(ip("192.0.2.1"), now));
+        assert!(!limiter.check_at(ip("192.0.2.1"), now));
+        assert!(limiter.check_at(ip("192.0.2.2"), now));
+        assert!(limiter.check_at(None, now));
+        assert!(limiter.check_at(ip("192.0.2.1"), now + Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn per_ip_rejection_does_not_use_global_quota() {
+        let limiter = RateLimiter::new()
+            .set_global(Quota::new(2, Duration::from_secs(60)))
+            .set_per_ip(Quota::new(1, Duration::from_secs(60)));
+        let now = Instant::now();
+
+        assert!(limiter.check_at(ip("192.0.2.1"), now));
+        assert!(!limiter.check_at(ip("192.0.2.1"), now));
+        assert!(limiter.check_at(ip("192.0.2.2"), now));
+        assert!(!limiter.check_at(ip("192.0.2.3"), now));
+    }
+
+    #[test]
+    fn full_buckets_are_removed_when_too_many_ips_are_tracked() {
+        let limiter = RateLimiter::new().set_per_ip(Quota::new(1, Duration::from_secs(1)));
+        let now = Instant::now();
+
+        for i in 0..MAX_TRACKED_IPS as u32 {
+            let remoteip = Remoteip::from(std::net::Ipv4Addr::from(i));
+            assert!(limiter.check_at(Some(remoteip), now));
+        }
+        assert!(limiter.check_at(ip("192.0.2.1"), now + Duration::from_secs(1)));
+
+        let (_, buckets) = limiter.per_ip.as_ref().unwrap();
+        assert_eq!(buckets.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn global_rejection_does_not_use_per_ip_quota() {
+        let limiter = RateLimiter::new()
+            .set_global(Quota::new(1, Duration::from_secs(10)))
+            .set_per_ip(Quota::new(1, Duration::from_secs(60)));
+        let now = Instant::now();
+
+        assert!(limiter.check_at(ip("192.0.2.1"), now));
+        assert!(!limiter.check_at(ip("192.0.2.2"), now));
+        assert!(limiter.check_at(ip("192.0.2.2"), now + Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn least_recently_used_ips_are_removed_when_none_are_full() {
+        let limiter = RateLimiter::new().set_per_ip(Quota::new(2, Duration::from_secs(60)));
+        let now = Instant::now();
+
+        for i in 0..MAX_TRACKED_IPS as u32 {
+            let remoteip = Remoteip::from(std::net::Ipv4Addr::from(i));
+            let at = now + Duration::from_millis(u64::from(i));
+            assert!(limiter.check_at(Some(remoteip), at));
+        }
+        let later = now + Duration::from_secs(11);
+        assert!(limiter.check_at(ip("192.0.2.1"), later));
+
+        let (_, buckets) = limiter.per_ip.as_ref().unwrap();
+        let buckets = buckets.lock().unwrap();
+        assert_eq!(buckets.len(), MAX_TRACKED_IPS / 2 + 1);
+        assert!(buckets.contains_key(&std::net::Ipv4Addr::from(MAX_TRACKED_IPS as u32 - 1).into()));
+        assert!(!buckets.contains_key(&std::net::Ipv4Addr::from(0).into()));
+    }
+}
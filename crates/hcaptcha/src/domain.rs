@@ -10,10 +10,31 @@ mod secret;
 mod secret_ext;
 mod sitekey;
 
-pub(crate) use client_response::ClientResponse;
-pub(crate) use remoteip::Remoteip;
+pub use client_response::ClientResponse;
+pub use remoteip::Remoteip;
 #[cfg(not(feature = "ext"))]
-pub(crate) use secret::Secret;
+pub use secret::Secret;
 #[cfg(feature = "ext")]
-pub(crate) use secret_ext::Secret;
-pub(crate) use sitekey::Sitekey;
+pub use secret_ext::Secret;
+pub use sitekey::Sitekey;
+
+use crate::Error;
+
+/// Convert a validation [Error] into a deserialization error that reports
+/// each [Code](crate::Code) found.
+///
+/// The value that failed validation is not included in the message so
+/// that a malformed secret is not written to the logs.
+pub(crate) fn de_error<E: serde::de::Error>(name: &str, error: Error) -> E {
+    match error {
+        Error::Codes(codes) => {
+            let mut codes: Vec<String> = codes
+                .iter()
+                .map(|code| format!("{} ({code})", code.as_str()))
+                .collect();
+            codes.sort();
+            E::custom(format_args!("invalid {name}: {}", codes.join(", ")))
+        }
+        error => E::custom(format_args!("invalid {name}: {error}")),
+    }
+}
@@ -2,14 +2,25 @@
 //
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use super::de_error;
 use crate::{Code, Error};
+use serde::{Deserialize, Deserializer};
 use std::collections::HashSet;
 use std::fmt;
+use std::str::FromStr;
 
-#[derive(Debug, Default, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+/// The response token collected by the client from the Hcaptcha widget.
+///
+/// The token is checked to ensure that it is not empty or blank.
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize)]
 pub struct ClientResponse(String);
 
 impl ClientResponse {
+    /// Validate the string and return a ClientResponse.
+    ///
+    /// # Errors
+    ///
+    /// [Code::MissingResponse] is returned if the string is empty or blank.
     pub fn parse(s: String) -> Result<ClientResponse, Error> {
         if s.trim().is_empty() {
             let mut codes = HashSet::new();
@@ -21,11 +32,46 @@ impl ClientResponse {
         }
     }
 
+    /// The response token as a string slice.
     pub fn as_str(&self) -> &str {
         &self.0
     }
 }
 
+/// The string is validated as it is deserialized. A deserialization error
+/// reports the [Code]s for any validation failures.
+impl<'de> Deserialize<'de> for ClientResponse {
+    fn deserialize<D>(de: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(de)?;
+        ClientResponse::parse(s).map_err(|e| de_error("response", e))
+    }
+}
+
+impl FromStr for ClientResponse {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ClientResponse::parse(s.to_owned())
+    }
+}
+
+impl TryFrom<String> for ClientResponse {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        ClientResponse::parse(s)
+    }
+}
+
+impl AsRef<str> for ClientResponse {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
 impl fmt::Display for ClientResponse {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.0)
@@ -37,7 +83,7 @@ mod tests {
     use super::ClientResponse;
     use crate::Code;
     use crate::Error;
-    use claims::assert_err;
+    use claims::{assert_err, assert_ok};
 
     #[test]
     fn whitespace_only_names_are_rejected() {
@@ -65,4 +111,16 @@ mod tests {
         let response = ClientResponse("test_response".to_string());
         assert_eq!(response.as_str(), "test_response");
     }
+
+    #[test]
+    fn deserialize_rejects_blank_response() {
+        assert_err!(serde_json::from_str::<ClientResponse>("\"  \""));
+    }
+
+    #[test]
+    fn deserialize_accepts_response() {
+        let response = serde_json::from_str::<ClientResponse>("\"test_response\"");
+        assert_ok!(&response);
+        assert_eq!(response.unwrap().as_ref(), "test_response");
+    }
 }
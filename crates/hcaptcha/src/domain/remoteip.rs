@@ -2,14 +2,21 @@
 //
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use super::de_error;
 use crate::{Code, Error};
+use serde::{Deserialize, Deserializer};
 use std::collections::HashSet;
 use std::fmt;
-use std::net::{Ipv4Addr, Ipv6Addr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::str::FromStr;
 
-#[derive(Debug, Default, Clone, serde::Deserialize, serde::Serialize)]
-pub struct Remoteip(String);
+/// The ip address of the client that completed the captcha.
+///
+/// A string is accepted if it is a valid IPv4 or IPv6 address. The address
+/// is serialized and deserialized as a string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize)]
+#[serde(into = "String")]
+pub struct Remoteip(IpAddr);
 
 impl fmt::Display for Remoteip {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -18,15 +25,109 @@ impl fmt::Display for Remoteip {
 }
 
 impl Remoteip {
+    /// Create a Remoteip from an ip address.
+    ///
+    /// IPv4-mapped IPv6 addresses (`::ffff:a.b.c.d`) are stored as the
+    /// IPv4 address they map so that the same client is always reported
+    /// to the API in the same form.
+    pub fn new(ip: IpAddr) -> Self {
+        Remoteip(ip.to_canonical())
+    }
+
+    /// Validate the string and return a Remoteip.
+    ///
+    /// # Errors
+    ///
+    /// [Code::MissingUserIp] is returned if the string is empty or blank
+    /// and [Code::InvalidUserIp] if it is not an ip address.
     #[cfg_attr(
         feature = "trace",
         tracing::instrument(name = "Validate User IP.", skip(s), level = "debug")
     )]
     pub fn parse(s: String) -> Result<Self, Error> {
         empty_ip_string(&s)?;
-        invalid_ip_string(&s)?;
+        let ip = invalid_ip_string(&s)?;
 
-        Ok(Remoteip(s))
+        Ok(Remoteip::new(ip))
+    }
+
+    /// The ip address held by the Remoteip.
+    pub fn ip(&self) -> IpAddr {
+        self.0
+    }
+}
+
+impl From<IpAddr> for Remoteip {
+    fn from(ip: IpAddr) -> Self {
+        Remoteip::new(ip)
+    }
+}
+
+impl From<Ipv4Addr> for Remoteip {
+    fn from(ip: Ipv4Addr) -> Self {
+        Remoteip::new(IpAddr::V4(ip))
+    }
+}
+
+impl From<Ipv6Addr> for Remoteip {
+    fn from(ip: Ipv6Addr) -> Self {
+        Remoteip::new(IpAddr::V6(ip))
+    }
+}
+
+/// The port and any IPv6 scope id are discarded; only the ip address is
+/// reported to the API.
+impl From<SocketAddr> for Remoteip {
+    fn from(addr: SocketAddr) -> Self {
+        Remoteip::new(addr.ip())
+    }
+}
+
+/// The string is validated as it is deserialized. A deserialization error
+/// reports the [Code]s for any validation failures.
+impl<'de> Deserialize<'de> for Remoteip {
+    fn deserialize<D>(de: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(de)?;
+        Remoteip::parse(s).map_err(|e| de_error("remoteip", e))
+    }
+}
+
+impl FromStr for Remoteip {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Remoteip::parse(s.to_owned())
+    }
+}
+
+impl TryFrom<&str> for Remoteip {
+    type Error = Error;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        Remoteip::parse(s.to_owned())
+    }
+}
+
+impl TryFrom<String> for Remoteip {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Remoteip::parse(s)
+    }
+}
+
+impl From<Remoteip> for String {
+    fn from(remoteip: Remoteip) -> Self {
+        remoteip.to_string()
+    }
+}
+
+impl AsRef<IpAddr> for Remoteip {
+    fn as_ref(&self) -> &IpAddr {
+        &self.0
     }
 }
 
@@ -47,23 +148,25 @@ fn empty_ip_string(s: &str) -> Result<(), Error> {
     }
 }
 
+/// Parse the ip string, ignoring any IPv6 scope id (`fe80::1%eth0`) as
+/// it has no meaning outside of the host that received the request.
 #[cfg_attr(
     feature = "trace",
     tracing::instrument(name = "Return error if not an ip string.", skip(s), level = "debug")
 )]
-fn invalid_ip_string(s: &str) -> Result<(), Error> {
-    let invalid_ip4 = Ipv4Addr::from_str(s).is_err();
-    let invalid_ip6 = Ipv6Addr::from_str(s).is_err();
-    if invalid_ip4 && invalid_ip6 {
+fn invalid_ip_string(s: &str) -> Result<IpAddr, Error> {
+    let ip = match s.split_once('%') {
+        Some((ip, _scope)) => Ipv6Addr::from_str(ip).map(IpAddr::V6),
+        None => IpAddr::from_str(s),
+    };
+    ip.map_err(|_| {
         let mut codes = HashSet::new();
         codes.insert(Code::InvalidUserIp);
 
         #[cfg(feature = "trace")]
         tracing::debug!("UserIP string is invalid");
-        Err(Error::Codes(codes))
-    } else {
-        Ok(())
-    }
+        Error::Codes(codes)
+    })
 }
 
 #[cfg(test)]
@@ -72,6 +175,7 @@ mod tests {
     use crate::Code;
     use crate::Error;
     use claims::{assert_err, assert_ok};
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV6};
 
     #[test]
     fn whitespace_only_ip_strings_are_rejected() {
@@ -116,4 +220,59 @@ mod tests {
         let ip_string = mockd::internet::ipv6_address();
         assert_ok!(Remoteip::parse(ip_string));
     }
+
+    #[test]
+    fn ip6_string_is_normalised() {
+        let remoteip = Remoteip::parse("2001:0DB8:0000:0000:0000:0000:0000:0001".to_string());
+        assert_eq!(remoteip.unwrap().to_string(), "2001:db8::1");
+    }
+
+    #[test]
+    fn ip4_mapped_ip6_string_is_reported_as_ip4() {
+        let remoteip = Remoteip::parse("::ffff:192.0.2.10".to_string()).unwrap();
+        assert_eq!(remoteip.ip(), IpAddr::V4(Ipv4Addr::new(192, 0, 2, 10)));
+        assert_eq!(remoteip.to_string(), "192.0.2.10");
+    }
+
+    #[test]
+    fn scope_id_is_dropped_from_ip6_string() {
+        let remoteip = Remoteip::parse("fe80::1%eth0".to_string()).unwrap();
+        assert_eq!(remoteip.to_string(), "fe80::1");
+    }
+
+    #[test]
+    fn scope_id_is_rejected_on_ip4_string() {
+        assert_err!(Remoteip::parse("192.0.2.10%eth0".to_string()));
+    }
+
+    #[test]
+    fn socket_address_port_and_scope_are_dropped() {
+        let addr = SocketAddr::V6(SocketAddrV6::new(
+            Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1),
+            8080,
+            0,
+            3,
+        ));
+        let remoteip = Remoteip::from(addr);
+        assert_eq!(remoteip.to_string(), "fe80::1");
+    }
+
+    #[test]
+    fn ip_addr_and_string_give_the_same_remoteip() {
+        let ip = IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1));
+        let from_ip = Remoteip::from(ip);
+        let from_str = Remoteip::try_from("2001:db8:0:0:0:0:0:1").unwrap();
+        assert_eq!(from_ip, from_str);
+    }
+
+    #[test]
+    fn deserialize_rejects_invalid_ip_string() {
+        assert_err!(serde_json::from_str::<Remoteip>("\"1922.20\""));
+    }
+
+    #[test]
+    fn serialize_and_deserialize_as_string() {
+        let remoteip: Remoteip = serde_json::from_str("\"2001:db8::1\"").unwrap();
+        assert_eq!(serde_json::to_string(&remoteip).unwrap(), "\"2001:db8::1\"");
+    }
 }
@@ -2,11 +2,33 @@
 //
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use super::de_error;
 use crate::{Code, Error};
+use serde::{Deserialize, Deserializer};
 use std::collections::HashSet;
 use std::fmt;
+use std::str::FromStr;
 
-#[derive(Debug, Default, Clone, serde::Serialize)]
+/// The secret key for the hcaptcha account.
+///
+/// The secret is checked to ensure that it is not empty or blank. Enable the
+/// `ext` feature for additional checks of the format of the secret.
+///
+/// # Example
+///
+/// Validate the secret when the configuration is loaded.
+/// ```
+///     use hcaptcha::Secret;
+///
+///     #[derive(serde::Deserialize)]
+///     struct Config {
+///         secret: Secret,
+///     }
+///
+///     let config: Result<Config, _> = serde_json::from_str(r#"{"secret": " "}"#);
+///     assert!(config.is_err());
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Serialize)]
 pub struct Secret(String);
 
 impl fmt::Display for Secret {
@@ -16,6 +38,11 @@ impl fmt::Display for Secret {
 }
 
 impl Secret {
+    /// Validate the string and return a Secret.
+    ///
+    /// # Errors
+    ///
+    /// [Code::MissingSecret] is returned if the string is empty or blank.
     #[cfg_attr(
         feature = "trace",
         tracing::instrument(name = "Simple check of secret.", skip(s), level = "debug")
@@ -32,6 +59,45 @@ impl Secret {
             Ok(Secret(s))
         }
     }
+
+    /// The secret as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// The string is validated as it is deserialized. A deserialization error
+/// reports the [Code]s for any validation failures.
+impl<'de> Deserialize<'de> for Secret {
+    fn deserialize<D>(de: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(de)?;
+        Secret::parse(s).map_err(|e| de_error("secret", e))
+    }
+}
+
+impl FromStr for Secret {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Secret::parse(s.to_owned())
+    }
+}
+
+impl TryFrom<String> for Secret {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Secret::parse(s)
+    }
+}
+
+impl AsRef<str> for Secret {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
 }
 
 #[cfg(test)]
@@ -41,6 +107,12 @@ mod tests {
     use crate::Error;
     use claims::{assert_err, assert_ok};
 
+    #[derive(Debug, serde::Deserialize)]
+    struct Config {
+        #[allow(dead_code)]
+        secret: Secret,
+    }
+
     #[test]
     fn whitespace_only_secrets_are_rejected() {
         let secret = " ".to_string();
@@ -85,4 +157,23 @@ mod tests {
         let secret = "ES_a5e0b5406e2b4c939ce48946389463894638473b1c".to_string();
         assert_ok!(Secret::parse(secret));
     }
+
+    #[test]
+    fn deserialize_rejects_invalid_secret() {
+        assert_err!(serde_json::from_str::<Secret>("\" \""));
+    }
+
+    #[test]
+    fn secret_from_str_matches_input() {
+        let secret = "0x0000000123456789abcdefABCDEF000000000000";
+        let parsed: Secret = secret.parse().unwrap();
+        assert_eq!(parsed.as_ref(), secret);
+    }
+
+    #[test]
+    fn config_with_blank_secret_reports_code() {
+        let err = serde_json::from_str::<Config>(r#"{"secret": "  "}"#).unwrap_err();
+
+        assert!(err.to_string().contains("missing-input-secret"));
+    }
 }
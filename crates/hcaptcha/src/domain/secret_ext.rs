@@ -2,14 +2,36 @@
 //
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use super::de_error;
 use crate::{Code, Error};
+use serde::{Deserialize, Deserializer};
 use std::collections::HashSet;
 use std::fmt;
+use std::str::FromStr;
 
 const SECRET_LEN_V1: usize = 42;
 const SECRET_LEN_V2: usize = 35;
 
-#[derive(Debug, Default, Clone, serde::Serialize)]
+/// The secret key for the hcaptcha account.
+///
+/// The secret is checked to ensure that it is not empty or blank and that
+/// it matches the length and hex format of a known version of secret.
+///
+/// # Example
+///
+/// Validate the secret when the configuration is loaded.
+/// ```
+///     use hcaptcha::Secret;
+///
+///     #[derive(serde::Deserialize)]
+///     struct Config {
+///         secret: Secret,
+///     }
+///
+///     let config: Result<Config, _> = serde_json::from_str(r#"{"secret": " "}"#);
+///     assert!(config.is_err());
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Serialize)]
 pub struct Secret(String);
 
 impl fmt::Display for Secret {
@@ -19,6 +41,15 @@ impl fmt::Display for Secret {
 }
 
 impl Secret {
+    /// Validate the string and return a Secret.
+    ///
+    /// # Errors
+    ///
+    /// [Code::MissingSecret] is returned if the string is empty or blank,
+    /// [Code::SecretVersionUnknown] if the version of the secret is not
+    /// recognised and [Code::InvalidSecretExtWrongLen] or
+    /// [Code::InvalidSecretExtNotHex] if the secret is not in the format
+    /// of its version.
     #[cfg_attr(
         feature = "trace",
         tracing::instrument(name = "Extended check of secret.", skip(s), level = "debug")
@@ -30,7 +61,7 @@ impl Secret {
         }
     }
 
-    pub fn parse_v1(s: String) -> Result<Self, Error> {
+    fn parse_v1(s: String) -> Result<Self, Error> {
         let is_wrong_length = s.len() != SECRET_LEN_V1;
         let is_not_a_hex_string = !is_hex_string(&s);
         let mut codes = HashSet::new();
@@ -49,7 +80,7 @@ impl Secret {
         }
     }
 
-    pub fn parse_v2(s: String) -> Result<Self, Error> {
+    fn parse_v2(s: String) -> Result<Self, Error> {
         let is_wrong_length = s.len() != SECRET_LEN_V2;
         let hex_portion = s.replace("ES_", "0x");
         let is_not_a_hex_string = !is_hex_string(&hex_portion);
@@ -68,6 +99,45 @@ impl Secret {
             Err(Error::Codes(codes))
         }
     }
+
+    /// The secret as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// The string is validated as it is deserialized. A deserialization error
+/// reports the [Code]s for any validation failures.
+impl<'de> Deserialize<'de> for Secret {
+    fn deserialize<D>(de: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(de)?;
+        Secret::parse(s).map_err(|e| de_error("secret", e))
+    }
+}
+
+impl FromStr for Secret {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Secret::parse(s.to_owned())
+    }
+}
+
+impl TryFrom<String> for Secret {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Secret::parse(s)
+    }
+}
+
+impl AsRef<str> for Secret {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
 }
 
 #[cfg_attr(
@@ -75,7 +145,7 @@ impl Secret {
     tracing::instrument(name = "Check for hex string.", skip(s), level = "debug")
 )]
 fn is_hex_string(s: &str) -> bool {
-    let start_is_valid = &s[0..2] == "0x";
+    let start_is_valid = s.starts_with("0x");
     let string_is_valid = hex::decode(s.trim_start_matches("0x")).is_ok();
 
     start_is_valid && string_is_valid
@@ -97,10 +167,9 @@ impl SecretVersions {
             tracing::debug!("Extended check found errors in secret string: {:?}", &codes);
             return Err(Error::Codes(codes));
         }
-        let start = &s[0..2];
-        match start {
-            "0x" => Ok(SecretVersions::V1(s)),
-            "ES" => Ok(SecretVersions::V2(s)),
+        match s.get(0..2) {
+            Some("0x") => Ok(SecretVersions::V1(s)),
+            Some("ES") => Ok(SecretVersions::V2(s)),
             _ => {
                 codes.insert(Code::SecretVersionUnknown);
                 #[cfg(feature = "trace")]
@@ -118,6 +187,12 @@ mod tests {
     use crate::Error;
     use claims::assert_err;
 
+    #[derive(Debug, serde::Deserialize)]
+    struct Config {
+        #[allow(dead_code)]
+        secret: Secret,
+    }
+
     #[test]
     fn whitespace_only_secrets_are_rejected() {
         let secret = " ".to_string();
@@ -174,4 +249,48 @@ mod tests {
             assert!(codes.contains(&Code::InvalidSecretExtWrongLen));
         }
     }
+
+    #[test]
+    fn deserialize_rejects_invalid_secret() {
+        assert_err!(serde_json::from_str::<Secret>("\" \""));
+    }
+
+    #[test]
+    fn secret_from_str_matches_input() {
+        let secret = "0x0000000123456789abcdefABCDEF000000000000";
+        let parsed: Secret = secret.parse().unwrap();
+        assert_eq!(parsed.as_ref(), secret);
+    }
+
+    #[test]
+    fn single_character_secret_is_rejected() {
+        let secret = "0".to_string();
+        if let Err(Error::Codes(hs)) = Secret::parse(secret) {
+            assert!(hs.contains(&Code::SecretVersionUnknown));
+        }
+    }
+
+    #[test]
+    fn config_with_wrong_length_secret_reports_code() {
+        let err = serde_json::from_str::<Config>(r#"{"secret": "0x123456"}"#).unwrap_err();
+        let message = err.to_string();
+
+        assert!(message.contains("invalid secret"));
+        assert!(message.contains("invalid-secret-ext-wrong-len"));
+        assert!(!message.contains("0x123456"));
+    }
+
+    #[test]
+    fn config_with_unknown_secret_version_reports_code() {
+        let err = serde_json::from_str::<Config>(r#"{"secret": "AB_123456"}"#).unwrap_err();
+
+        assert!(err.to_string().contains("secret-version-unknown"));
+    }
+
+    #[test]
+    fn config_with_valid_secret_is_loaded() {
+        let config =
+            serde_json::from_str::<Config>(r#"{"secret": "ES_a5e0b5406e2b4c939ce4894638946389"}"#);
+        assert!(config.is_ok());
+    }
 }
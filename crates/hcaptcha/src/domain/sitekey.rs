@@ -2,13 +2,18 @@
 //
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use super::de_error;
 use crate::{Code, Error};
+use serde::{Deserialize, Deserializer};
 use std::collections::HashSet;
 use std::fmt;
 use std::str::FromStr;
 use uuid::Uuid;
 
-#[derive(Debug, Default, Clone, serde::Deserialize, serde::Serialize)]
+/// The sitekey for the site on which the captcha was completed.
+///
+/// The sitekey must be a uuid.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, serde::Serialize)]
 pub struct Sitekey(String);
 
 impl fmt::Display for Sitekey {
@@ -18,6 +23,12 @@ impl fmt::Display for Sitekey {
 }
 
 impl Sitekey {
+    /// Validate the string and return a Sitekey.
+    ///
+    /// # Errors
+    ///
+    /// [Code::MissingSiteKey] is returned if the string is empty or blank
+    /// and [Code::InvalidSiteKey] if it is not a uuid.
     #[cfg_attr(
         feature = "trace",
         tracing::instrument(name = "Validate Site Key.", skip(s), level = "debug")
@@ -28,6 +39,59 @@ impl Sitekey {
 
         Ok(Sitekey(s))
     }
+
+    /// The sitekey as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// The string is validated as it is deserialized. A deserialization error
+/// reports the [Code]s for any validation failures.
+impl<'de> Deserialize<'de> for Sitekey {
+    fn deserialize<D>(de: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(de)?;
+        Sitekey::parse(s).map_err(|e| de_error("sitekey", e))
+    }
+}
+
+impl FromStr for Sitekey {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Sitekey::parse(s.to_owned())
+    }
+}
+
+impl From<Uuid> for Sitekey {
+    fn from(uuid: Uuid) -> Self {
+        Sitekey(uuid.hyphenated().to_string())
+    }
+}
+
+impl TryFrom<&str> for Sitekey {
+    type Error = Error;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        Sitekey::parse(s.to_owned())
+    }
+}
+
+impl TryFrom<String> for Sitekey {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Sitekey::parse(s)
+    }
+}
+
+impl AsRef<str> for Sitekey {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
 }
 
 #[cfg_attr(
@@ -70,6 +134,7 @@ mod tests {
     use crate::Code;
     use crate::Error;
     use claims::{assert_err, assert_ok};
+    use uuid::Uuid;
 
     // const CYAN: &str = "\u{001b}[36m";
     // const RESET: &str = "\u{001b}[0m";
@@ -111,4 +176,36 @@ mod tests {
 
         assert_ok!(Sitekey::parse(sitekey));
     }
+
+    #[test]
+    fn uuid_converts_to_sitekey() {
+        let uuid = Uuid::new_v4();
+        let sitekey = Sitekey::from(uuid);
+
+        assert_eq!(sitekey.to_string(), uuid.to_string());
+        assert_ok!(Sitekey::parse(sitekey.to_string()));
+    }
+
+    #[test]
+    fn deserialize_rejects_invalid_sitekey() {
+        assert_err!(serde_json::from_str::<Sitekey>("\"1922.20\""));
+    }
+
+    #[test]
+    fn from_str_accepts_valid_sitekey() {
+        let sitekey = mockd::unique::uuid_v4();
+        let parsed: Sitekey = sitekey.parse().unwrap();
+
+        assert_eq!(parsed.as_ref(), sitekey);
+    }
+
+    #[test]
+    fn deserialize_error_reports_code() {
+        let err = serde_json::from_str::<Sitekey>("\"not-a-uuid\"").unwrap_err();
+        let message = err.to_string();
+
+        assert!(message.contains("invalid sitekey"));
+        assert!(message.contains("invalid-input-sitekey"));
+        assert!(!message.contains("not-a-uuid"));
+    }
 }
@@ -20,8 +20,28 @@ pub enum Error {
     #[error("{0:?}")]
     Codes(HashSet<Code>),
     /// Error returned by reqwest
+    #[cfg(all(feature = "reqwest", not(target_os = "wasi")))]
     #[error("{0}")]
     Reqwest(#[from] reqwest::Error),
+    /// Error returned by a [Transport](crate::Transport) other than reqwest
+    /// when the request could not be sent or the response read.
+    #[error("{0}")]
+    Transport(Box<dyn std::error::Error + Send + Sync>),
+    /// Error returned by a [Transport](crate::Transport) other than reqwest
+    /// when the connection to the endpoint could not be established, for
+    /// example when the host name does not resolve, the connection is
+    /// refused or connecting timed out. The request was not sent.
+    #[error("{0}")]
+    Connect(Box<dyn std::error::Error + Send + Sync>),
+    /// Error returned by a [Transport](crate::Transport) other than reqwest
+    /// when the endpoint did not respond in time.
+    #[error("{0}")]
+    Timeout(Box<dyn std::error::Error + Send + Sync>),
+    /// The endpoint answered with a server error or `429 Too Many Requests`,
+    /// or with another error status and a body that is not a siteverify
+    /// response.
+    #[error("Error status from the endpoint: HTTP status {0}")]
+    Status(u16),
     /// Error returned by io
     #[error("{0}")]
     Io(#[from] io::Error),
@@ -37,6 +57,25 @@ pub enum Error {
     /// Error returned by url parser
     #[error("{0}")]
     Url(#[from] url::ParseError),
+    /// Error returned when reading an environment variable
+    #[error("{0}")]
+    Env(#[from] std::env::VarError),
+    /// Error returned by a [SecretProvider](crate::SecretProvider) that
+    /// fetches the secret from an external store.
+    #[error("{0}")]
+    SecretProvider(Box<dyn std::error::Error + Send + Sync>),
+    /// The verification was rejected by the client's
+    /// [RateLimiter](crate::RateLimiter) without calling the API.
+    #[error("Rate limit exceeded")]
+    RateLimited,
+    /// The verification waited longer than the queue timeout for one of the
+    /// requests in flight to complete.
+    #[error("Too many requests in flight")]
+    Overloaded,
+    /// The verification was rejected without calling the API because the
+    /// client's [CircuitBreaker](crate::CircuitBreaker) is open.
+    #[error("Circuit breaker is open")]
+    CircuitOpen,
 }
 
 /// Error code mapping for the error responses from the hcaptcha API.
@@ -78,6 +117,17 @@ pub enum Code {
     /// hcaptcha = {version = "2.3.0", default-features = false, features = [rustls-backend]}
     /// ```
     SecretVersionUnknown,
+    /// The hostname in the response is not one of the hostnames allowed by
+    /// the [Policy](crate::Policy).
+    HostnameMismatch,
+    /// The score in the response is above the threshold set by the
+    /// [Policy](crate::Policy), or no score was returned.
+    ScoreThresholdExceeded,
+    /// The verification pass is malformed, was not signed with the key of
+    /// the verification cache or was issued to another remote ip.
+    InvalidPass,
+    /// The verification pass is older than its time to live.
+    ExpiredPass,
     /// Collect any new error codes issued by the API.
     Unknown(String),
 }
@@ -103,12 +153,19 @@ impl<'de> Deserialize<'de> for Code {
     }
 }
 
-impl Serialize for Code {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        serializer.serialize_str(match self {
+impl Code {
+    /// The error code string used by the hcaptcha API, or by this library
+    /// for the codes it reports when validating inputs.
+    ///
+    /// # Example
+    ///
+    /// ```
+    ///     use hcaptcha::Code;
+    ///
+    ///     assert_eq!(Code::InvalidAlreadySeen.as_str(), "invalid-or-already-seen-response");
+    /// ```
+    pub fn as_str(&self) -> &str {
+        match self {
             Code::MissingSecret => "missing-input-secret",
             Code::InvalidSecret => "invalid-input-secret",
             Code::MissingUserIp => "missing-input-user-ip",
@@ -123,8 +180,21 @@ impl Serialize for Code {
             Code::SecretVersionUnknown => "secret-version-unknown",
             Code::InvalidSecretExtNotHex => "invalid-secret-ext-not-hex",
             Code::InvalidSecretExtWrongLen => "invalid-secret-ext-wrong-len",
+            Code::HostnameMismatch => "hostname-mismatch",
+            Code::ScoreThresholdExceeded => "score-threshold-exceeded",
+            Code::InvalidPass => "invalid-verification-pass",
+            Code::ExpiredPass => "expired-verification-pass",
             Code::Unknown(s) => s.as_str(),
-        })
+        }
+    }
+}
+
+impl Serialize for Code {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.as_str())
     }
 }
 
@@ -160,6 +230,14 @@ impl fmt::Display for Code {
             Code::SecretVersionUnknown => {
                 write!(f, "The version of the site secret is not recognise.")
             }
+            Code::HostnameMismatch => {
+                write!(f, "The hostname is not allowed by the policy.")
+            }
+            Code::ScoreThresholdExceeded => {
+                write!(f, "The score is above the threshold set by the policy.")
+            }
+            Code::InvalidPass => write!(f, "The verification pass is invalid."),
+            Code::ExpiredPass => write!(f, "The verification pass has expired."),
             Code::Unknown(e) => write!(f, "Unknown error: {e}"),
         }
     }
@@ -248,6 +326,30 @@ mod tests {
         assert_ser_tokens(&code, &[Token::Str("invalid-secret-ext-not-hex")]);
     }
 
+    #[test]
+    fn test_serialize_hostname_mismatch() {
+        let code = Code::HostnameMismatch;
+        assert_ser_tokens(&code, &[Token::Str("hostname-mismatch")]);
+    }
+
+    #[test]
+    fn test_serialize_score_threshold_exceeded() {
+        let code = Code::ScoreThresholdExceeded;
+        assert_ser_tokens(&code, &[Token::Str("score-threshold-exceeded")]);
+    }
+
+    #[test]
+    fn test_serialize_invalid_pass() {
+        let code = Code::InvalidPass;
+        assert_ser_tokens(&code, &[Token::Str("invalid-verification-pass")]);
+    }
+
+    #[test]
+    fn test_serialize_expired_pass() {
+        let code = Code::ExpiredPass;
+        assert_ser_tokens(&code, &[Token::Str("expired-verification-pass")]);
+    }
+
     #[test]
     fn test_serialize_unknown_variant() {
         let code = Code::Unknown("unexpected-error".to_string());
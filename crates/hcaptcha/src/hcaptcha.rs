@@ -3,16 +3,245 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 //! Hcaptcha Trait
-use crate::{Error, Response};
-// use async_trait::async_trait;
+use crate::{Client, Code, Error, Response};
+use std::collections::HashSet;
 use std::{future::Future, pin::Pin};
 
+/// Future returned by [Hcaptcha::valid_response] and [Hcaptcha::verify].
+///
+/// The future is `Send` so that it can be awaited in a spawned task or a
+/// handler on a multi-threaded executor. On `wasm32` targets, where the
+/// HTTP client is not `Send`, the future is not `Send` either.
+#[cfg(not(target_arch = "wasm32"))]
+pub type HcaptchaFuture = Pin<Box<dyn Future<Output = Result<Response, Error>> + Send>>;
+
+/// Future returned by [Hcaptcha::valid_response] and [Hcaptcha::verify].
+///
+/// The future is `Send` so that it can be awaited in a spawned task or a
+/// handler on a multi-threaded executor. On `wasm32` targets, where the
+/// HTTP client is not `Send`, the future is not `Send` either.
+#[cfg(target_arch = "wasm32")]
+pub type HcaptchaFuture = Pin<Box<dyn Future<Output = Result<Response, Error>>>>;
+
+/// Future returned by [Hcaptcha::valid_response_with], which borrows the
+/// shared [Client].
+///
+/// The future is `Send` except on `wasm32` targets, as for
+/// [HcaptchaFuture].
+#[cfg(not(target_arch = "wasm32"))]
+pub type HcaptchaFutureWith<'a> =
+    Pin<Box<dyn Future<Output = Result<Response, Error>> + Send + 'a>>;
+
+/// Future returned by [Hcaptcha::valid_response_with], which borrows the
+/// shared [Client].
+///
+/// The future is `Send` except on `wasm32` targets, as for
+/// [HcaptchaFuture].
+#[cfg(target_arch = "wasm32")]
+pub type HcaptchaFutureWith<'a> = Pin<Box<dyn Future<Output = Result<Response, Error>> + 'a>>;
+
 /// Hcaptcha trait
 pub trait Hcaptcha {
     /// valid response function
-    fn valid_response(
+    ///
+    /// A new [Client] is created for each call, connecting to `uri` if it
+    /// is supplied.
+    fn valid_response(&self, secret: &str, uri: Option<String>) -> HcaptchaFuture;
+
+    /// Verify the response with a shared [Client].
+    ///
+    /// The client's connection pool and configuration are reused for each
+    /// call, so a single client can serve every form submission.
+    ///
+    /// The derive macro generates this method to verify with the shared
+    /// client. The default implementation, for types that implement the
+    /// trait by hand, calls [Hcaptcha::valid_response] with the url of the
+    /// client, so only the url of the shared client is used.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    ///     use hcaptcha::{Client, Hcaptcha};
+    ///
+    ///     #[derive(Hcaptcha)]
+    ///     struct ContactForm {
+    ///         #[captcha]
+    ///         token: String,
+    ///     }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), hcaptcha::Error> {
+    /// #   let secret = "0x123456789abcde0f123456789abcdef012345678";
+    ///     let client = Client::new(); // Create once
+    ///
+    ///     let form = ContactForm { token: "response_token".to_string() };
+    ///     let response = form.valid_response_with(&client, secret).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn valid_response_with<'a>(&self, client: &'a Client, secret: &str) -> HcaptchaFutureWith<'a> {
+        self.valid_response(secret, Some(client.url().to_string()))
+    }
+
+    /// Verify the response with the settings declared on the type.
+    ///
+    /// The derive macro generates this method from the `#[hcaptcha(...)]`
+    /// attribute. The secret is read from the `secret_env` environment
+    /// variable, the request is sent to `url` and the response is checked
+    /// against the `hostnames` and `max_score`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    ///     use hcaptcha::Hcaptcha;
+    ///
+    ///     #[derive(Hcaptcha)]
+    ///     #[hcaptcha(secret_env = "HCAPTCHA_SECRET", hostnames = ["example.com"], max_score = 0.7)]
+    ///     struct ContactForm {
+    ///         #[captcha]
+    ///         token: String,
+    ///     }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), hcaptcha::Error> {
+    ///     let form = ContactForm { token: "response_token".to_string() };
+    ///     let response = form.verify().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// [Error::Env] is returned if the environment variable is not set and
+    /// [Code::HostnameMismatch] or [Code::ScoreThresholdExceeded] if the
+    /// response fails the checks. Types without the `#[hcaptcha(...)]`
+    /// attribute return [Code::MissingSecret].
+    fn verify(&self) -> HcaptchaFuture {
+        let mut codes = HashSet::new();
+        codes.insert(Code::MissingSecret);
+        Box::pin(async { Err(Error::Codes(codes)) })
+    }
+
+    /// Verify the response with a shared blocking client and wait for the
+    /// result.
+    ///
+    /// This is the blocking equivalent of [Hcaptcha::valid_response_with]
+    /// for code that does not run an async runtime.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    ///     use hcaptcha::Hcaptcha;
+    ///
+    ///     #[derive(Hcaptcha)]
+    ///     struct ContactForm {
+    ///         #[captcha]
+    ///         token: String,
+    ///     }
+    ///
+    /// # fn main() -> Result<(), hcaptcha::Error> {
+    /// #   let secret = "0x123456789abcde0f123456789abcdef012345678";
+    ///     let client = hcaptcha::blocking::Client::new(); // Create once
+    ///
+    ///     let form = ContactForm { token: "response_token".to_string() };
+    ///     let response = form.valid_response_blocking(&client, secret)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns the error from [Hcaptcha::valid_response_with].
+    ///
+    #[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "blocking")))]
+    fn valid_response_blocking(
         &self,
+        client: &crate::blocking::Client,
         secret: &str,
-        uri: Option<String>,
-    ) -> Pin<Box<dyn Future<Output = Result<Response, Error>>>>;
+    ) -> Result<Response, Error> {
+        client.block_on(self.valid_response_with(client.inner(), secret))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_send<T: Send>(_: &T) {}
+
+    struct Form;
+
+    impl Hcaptcha for Form {
+        fn valid_response(&self, secret: &str, _uri: Option<String>) -> HcaptchaFuture {
+            let captcha = crate::Captcha::new("response_token").unwrap();
+            let request = crate::Request::new(secret, captcha).unwrap();
+            Box::pin(Client::new().verify(request))
+        }
+
+        fn valid_response_with<'a>(
+            &self,
+            client: &'a Client,
+            secret: &str,
+        ) -> HcaptchaFutureWith<'a> {
+            let captcha = crate::Captcha::new("response_token").unwrap();
+            let request = crate::Request::new(secret, captcha).unwrap();
+            Box::pin(client.verify_request(request))
+        }
+    }
+
+    #[cfg(feature = "blocking")]
+    #[cfg_attr(miri, ignore)]
+    #[test]
+    fn valid_response_blocking_waits_for_the_response() {
+        // A site registry or provider is not needed to reach the endpoint,
+        // so an unreachable url gives a connect error without a network.
+        let client = crate::blocking::Client::new_with("http://127.0.0.1:9/siteverify").unwrap();
+        let response =
+            Form.valid_response_blocking(&client, "0x123456789abcde0f123456789abcdef012345678");
+        assert!(matches!(response, Err(Error::Connect(_))));
+    }
+
+    #[tokio::test]
+    async fn verify_without_settings_is_missing_secret() {
+        match Form.verify().await {
+            Err(Error::Codes(codes)) => assert!(codes.contains(&Code::MissingSecret)),
+            _ => panic!("Expected the missing secret code"),
+        }
+    }
+
+    #[test]
+    fn valid_response_futures_are_send() {
+        let future = Form.valid_response("0x123456789abcde0f123456789abcdef012345678", None);
+        assert_send(&future);
+
+        let client = Client::new();
+        let future =
+            Form.valid_response_with(&client, "0x123456789abcde0f123456789abcdef012345678");
+        assert_send(&future);
+    }
+
+    #[test]
+    fn valid_response_with_defaults_to_the_client_url() {
+        #[derive(Default)]
+        struct ByHand {
+            uri: std::sync::Mutex<Option<String>>,
+        }
+
+        impl Hcaptcha for ByHand {
+            fn valid_response(&self, _secret: &str, uri: Option<String>) -> HcaptchaFuture {
+                *self.uri.lock().unwrap() = uri;
+                Box::pin(async { Err(Error::Codes(HashSet::new())) })
+            }
+        }
+
+        let form = ByHand::default();
+        let client = Client::new_with("https://verify.example.com/siteverify").unwrap();
+        drop(form.valid_response_with(&client, "0x123456789abcde0f123456789abcdef012345678"));
+
+        assert_eq!(
+            form.uri.lock().unwrap().as_deref(),
+            Some("https://verify.example.com/siteverify")
+        );
+    }
 }
@@ -265,6 +265,7 @@
 //!
 //! The default library includes extended validation for the secret field and use of rustls TLS as the TLS backend.
 //! Disable this validation by setting default-features = false and enable rustls with features=["nativetls-backend"].
+//! Without the default features no HTTP client is included, so enable a TLS backend or the `ureq` feature as well.
 //!
 //! ```toml
 //! [dependency]
@@ -275,6 +276,13 @@
 //! * `enterprise` - Enable methods to access enterprise service fields in the `Response`
 //! * `ext` - Enables extended validation of secret
 //! * `trace` - Enables tracing instrumentation on all functions. Traces are logged at the debug level. The value of the secret is not logged.
+//! * `verification-cache` - Enables the `VerificationCache` to issue signed passes for successful verifications
+//! * `blocking` - Enables the `blocking::Client` and `Hcaptcha::valid_response_blocking` to verify without an async runtime, sending with the `UreqTransport`
+//! * `hyper` - Enables the `HyperTransport` to send requests with a hyper client
+//! * `reqwest` - Enables the `ReqwestTransport`, the default transport, to send requests with a reqwest client
+//! * `tokio` - Enables the `TokioTimer` to wait for timeouts on the tokio time driver
+//! * `ureq` - Enables the `UreqTransport` to send requests with a ureq agent, the default transport without the `reqwest` feature
+//! * `wasi-http` - Enables the `WasiHttpTransport`, the default transport on wasi targets where reqwest is not available
 //! * `nativetls-backend` - Enables native-tls backend in reqwest
 //! * `rustls-backend` - Enables rustls backend in reqwest
 //!
@@ -282,22 +290,73 @@
 //!
 //! This version of hcaptcha requires Rust v1.88 or later.
 
+#[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "blocking")))]
+pub mod blocking;
 mod captcha;
 mod client;
-#[doc(hidden)]
-pub(crate) mod domain;
+mod domain;
 mod error;
 mod hcaptcha;
+mod policy;
 mod request;
 mod response;
+mod secret_provider;
+mod site_registry;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+mod timer;
+mod token_store;
+mod transport;
+#[cfg(feature = "verification-cache")]
+mod verification_cache;
 
 pub use captcha::Captcha;
 pub use client::Client;
+pub use client::EndpointSelection;
+pub use client::FailureMode;
+pub use client::RemoteIpPrivacy;
 pub use client::VERIFY_URL;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub use client::{CircuitBreaker, CircuitState, Quota, RateLimiter};
+pub use domain::{ClientResponse, Remoteip, Secret, Sitekey};
 pub use error::Code;
 pub use error::Error;
+pub use policy::Policy;
 pub use request::Request;
 pub use response::Response;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub use secret_provider::CachedSecretProvider;
+pub use secret_provider::{EnvSecretProvider, FileSecretProvider, SecretProvider};
+pub use site_registry::{Site, SiteRegistry};
+#[cfg(not(target_arch = "wasm32"))]
+pub use timer::ThreadTimer;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub use timer::Timer;
+#[cfg(all(
+    feature = "tokio",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+))]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+pub use timer::TokioTimer;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub use token_store::MemoryTokenStore;
+pub use token_store::TokenStore;
+#[cfg(all(feature = "hyper", not(target_arch = "wasm32")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "hyper")))]
+pub use transport::HyperTransport;
+#[cfg(all(feature = "reqwest", not(target_os = "wasi")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "reqwest")))]
+pub use transport::ReqwestTransport;
+#[cfg(all(feature = "ureq", not(target_arch = "wasm32")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "ureq")))]
+pub use transport::UreqTransport;
+#[cfg(all(feature = "wasi-http", target_os = "wasi"))]
+#[cfg_attr(docsrs, doc(cfg(feature = "wasi-http")))]
+pub use transport::WasiHttpTransport;
+pub use transport::{Transport, TransportResponse};
+#[cfg(feature = "verification-cache")]
+#[cfg_attr(docsrs, doc(cfg(feature = "verification-cache")))]
+pub use verification_cache::{VerificationCache, VerificationPass};
 
-pub use crate::hcaptcha::Hcaptcha;
+pub use crate::hcaptcha::{Hcaptcha, HcaptchaFuture, HcaptchaFutureWith};
 pub use hcaptcha_derive::*;
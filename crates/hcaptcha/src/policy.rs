@@ -0,0 +1,185 @@
+// SPDX-FileCopyrightText: 2022 jerusdp
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Verification policy applied to a successful [Response].
+//!
+//! The Hcaptcha API confirms that the token is valid. A [Policy] adds the
+//! checks the site owner makes on the response: that the captcha was solved
+//! on one of the site's hostnames and, for enterprise accounts, that the
+//! score is below a threshold.
+
+use crate::{Code, Error, Response};
+use std::collections::HashSet;
+
+/// Checks made on a successful response from the Hcaptcha API.
+///
+/// An empty policy accepts every successful response.
+///
+/// # Example
+/// ```
+///     use hcaptcha::Policy;
+///
+///     let policy = Policy::new()
+///         .add_hostname("example.com")
+///         .add_hostname("www.example.com")
+///         .set_max_score(0.7);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, serde::Deserialize)]
+pub struct Policy {
+    /// Hostnames the captcha may be solved on. Any hostname is accepted if
+    /// the list is empty.
+    #[serde(default)]
+    hostnames: Vec<String>,
+    /// Highest score accepted. The score reports the likelihood of
+    /// malicious activity, so a higher score is a greater risk.
+    #[serde(default)]
+    max_score: Option<f32>,
+}
+
+impl Policy {
+    /// Create a policy that accepts every successful response.
+    pub fn new() -> Self {
+        Policy::default()
+    }
+
+    /// Add a hostname that the captcha may be solved on.
+    ///
+    /// Hostnames are compared without regard to case.
+    pub fn add_hostname(mut self, hostname: &str) -> Self {
+        self.hostnames.push(hostname.to_owned());
+        self
+    }
+
+    /// Set the highest score accepted.
+    ///
+    /// The score is only returned for enterprise accounts. If a threshold is
+    /// set and the response has no score the response is rejected.
+    pub fn set_max_score(mut self, max_score: f32) -> Self {
+        self.max_score = Some(max_score);
+        self
+    }
+
+    /// Check the response against the policy.
+    ///
+    /// # Errors
+    ///
+    /// [Code::HostnameMismatch] is returned if the hostname is not allowed
+    /// and [Code::ScoreThresholdExceeded] if the score is above the
+    /// threshold.
+    #[cfg_attr(
+        feature = "trace",
+        tracing::instrument(
+            name = "Check response against policy.",
+            skip(response),
+            level = "debug"
+        )
+    )]
+    pub fn check(&self, response: &Response) -> Result<(), Error> {
+        let mut codes = HashSet::new();
+
+        if !self.hostnames.is_empty() {
+            let allowed = response.hostname().is_some_and(|hostname| {
+                self.hostnames
+                    .iter()
+                    .any(|h| h.eq_ignore_ascii_case(&hostname))
+            });
+            if !allowed {
+                #[cfg(feature = "trace")]
+                tracing::debug!("Hostname {:?} is not allowed", response.hostname());
+                codes.insert(Code::HostnameMismatch);
+            }
+        }
+
+        if let Some(max_score) = self.max_score {
+            if !response.score.is_some_and(|score| score <= max_score) {
+                #[cfg(feature = "trace")]
+                tracing::debug!("Score {:?} is above {}", response.score, max_score);
+                codes.insert(Code::ScoreThresholdExceeded);
+            }
+        }
+
+        if codes.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Codes(codes))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::{assert_err, assert_ok};
+    use serde_json::json;
+
+    fn response(value: serde_json::Value) -> Response {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn codes(result: Result<(), Error>) -> HashSet<Code> {
+        match result {
+            Err(Error::Codes(codes)) => codes,
+            _ => panic!("Expected error codes"),
+        }
+    }
+
+    #[test]
+    fn empty_policy_accepts_response() {
+        let response = response(json!({ "success": true }));
+        assert_ok!(Policy::new().check(&response));
+    }
+
+    #[test]
+    fn hostname_is_matched_without_case() {
+        let response = response(json!({ "success": true, "hostname": "WWW.Example.com" }));
+        let policy = Policy::new()
+            .add_hostname("example.com")
+            .add_hostname("www.example.com");
+        assert_ok!(policy.check(&response));
+    }
+
+    #[test]
+    fn other_hostname_is_rejected() {
+        let response = response(json!({ "success": true, "hostname": "evil.example" }));
+        let policy = Policy::new().add_hostname("example.com");
+        assert!(codes(policy.check(&response)).contains(&Code::HostnameMismatch));
+    }
+
+    #[test]
+    fn missing_hostname_is_rejected() {
+        let response = response(json!({ "success": true }));
+        let policy = Policy::new().add_hostname("example.com");
+        assert_err!(policy.check(&response));
+    }
+
+    #[test]
+    fn score_at_threshold_is_accepted() {
+        let response = response(json!({ "success": true, "score": 0.5 }));
+        assert_ok!(Policy::new().set_max_score(0.5).check(&response));
+    }
+
+    #[test]
+    fn score_above_threshold_is_rejected() {
+        let response = response(json!({ "success": true, "score": 0.9 }));
+        let result = Policy::new().set_max_score(0.5).check(&response);
+        assert!(codes(result).contains(&Code::ScoreThresholdExceeded));
+    }
+
+    #[test]
+    fn missing_score_is_rejected() {
+        let response = response(json!({ "success": true }));
+        assert_err!(Policy::new().set_max_score(0.5).check(&response));
+    }
+
+    #[test]
+    fn deserialize_policy() {
+        let policy: Policy =
+            serde_json::from_value(json!({ "hostnames": ["example.com"], "max_score": 0.7 }))
+                .unwrap();
+        assert_eq!(
+            policy,
+            Policy::new().add_hostname("example.com").set_max_score(0.7)
+        );
+    }
+}
@@ -52,9 +52,10 @@
 //!
 //! ```
 
-use crate::domain::Secret;
+use crate::domain::{Remoteip, Secret, Sitekey};
 use crate::Captcha;
 use crate::Error;
+use crate::FailureMode;
 
 /// Capture the required and optional data for a call to the hcaptcha API
 #[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
@@ -65,6 +66,9 @@ pub struct Request {
     captcha: Captcha,
     /// The secret_key related to the sitekey used to capture the response.
     secret: Secret,
+    /// Failure mode for the request, overriding the client's failure mode.
+    #[serde(skip)]
+    failure_mode: Option<FailureMode>,
 }
 
 #[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
@@ -135,6 +139,7 @@ impl Request {
         Ok(Request {
             captcha,
             secret: Secret::parse(secret.to_owned())?,
+            failure_mode: None,
         })
     }
 
@@ -196,6 +201,54 @@ impl Request {
         Request::new(secret, captcha)
     }
 
+    /// Create a new Request from a validated secret
+    ///
+    /// # Input
+    ///
+    /// The Hcaptcha API has two mandatory parameters:
+    ///     `secret`:     The client's secret key as a [Secret]
+    ///     `captcha`:    [Captcha] (including response token)
+    ///
+    /// # Output
+    ///
+    /// As the [Secret] has already been validated the Request is always
+    /// returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    ///     use hcaptcha::{Captcha, Request, Secret};
+    /// # fn main() -> Result<(), hcaptcha::Error>{
+    ///     // Validate the secret once, e.g. when loading configuration.
+    ///     let secret: Secret = "0x123456789abcde0f123456789abcdef012345678".parse()?;
+    ///     let captcha = Captcha::new("thisisthelonglistofcharactersthatformsaresponse")?;
+    ///
+    ///     let request = Request::new_from_secret(secret, captcha);
+    /// # Ok(())
+    /// # }
+    ///  ```
+    /// # Logging
+    ///
+    /// If the tracing feature is enabled a debug level span is set for the
+    /// method.
+    /// The secret field will not be logged.
+    ///
+    #[cfg_attr(
+        feature = "trace",
+        tracing::instrument(
+            name = "Create new Request from Secret and Captcha.",
+            skip(secret),
+            level = "debug"
+        )
+    )]
+    pub fn new_from_secret(secret: Secret, captcha: Captcha) -> Request {
+        Request {
+            captcha,
+            secret,
+            failure_mode: None,
+        }
+    }
+
     /// Specify the optional ip address value
     ///
     /// Update client IP address.
@@ -255,6 +308,52 @@ impl Request {
         Ok(self)
     }
 
+    /// Specify the optional ip address value from an ip or socket address
+    ///
+    /// Update client IP address without converting it to a string first.
+    /// The port and any IPv6 scope id of a socket address are discarded.
+    ///
+    /// # Example
+    /// ```
+    ///     use hcaptcha::Request;
+    ///     use std::net::{IpAddr, Ipv6Addr};
+    /// # fn main() -> Result<(), hcaptcha::Error> {
+    ///     let secret = get_your_secret();         // your secret key
+    ///     let response = get_response();          // user's response token
+    ///     let remoteip = IpAddr::V6(Ipv6Addr::LOCALHOST); // user's ip address
+    ///
+    ///     let request = Request::new_from_response(&secret, &response)?
+    ///         .set_remoteip_addr(remoteip);
+    /// # Ok(())
+    /// # }
+    /// # fn get_your_secret() -> String {
+    /// #   "0x123456789abcde0f123456789abcdef012345678".to_string()
+    /// # }
+    /// # fn get_response() -> String {
+    /// #    "thisisthelonglistofcharactersthatformsaresponse".to_string()
+    /// # }
+    /// ```
+    ///
+    /// #Logging
+    ///
+    /// If the `trace` feature is enabled a debug level span is set for the
+    /// method.
+    /// The secret field is not logged.
+    ///
+    #[cfg_attr(
+        feature = "trace",
+        tracing::instrument(
+            name = "Request verification from hcaptcha.",
+            skip(self, remoteip),
+            fields(captcha = ?self.captcha),
+            level = "debug"
+        )
+    )]
+    pub fn set_remoteip_addr(mut self, remoteip: impl Into<Remoteip>) -> Self {
+        self.captcha.set_remoteip_addr(remoteip);
+        self
+    }
+
     /// Specify the optional sitekey value
     ///
     /// Update the sitekey.
@@ -322,6 +421,76 @@ impl Request {
         Ok(self)
     }
 
+    /// Specify the optional sitekey value from a uuid
+    ///
+    /// Update the sitekey without converting it to a string first.
+    ///
+    /// # Example
+    /// ```
+    ///     use hcaptcha::Request;
+    ///     use uuid::Uuid;
+    /// # fn main() -> Result<(), hcaptcha::Error> {
+    ///     let secret = get_your_secret();     // your secret key
+    ///     let response = get_response();      // user's response token
+    ///     let sitekey = Uuid::new_v4();       // your site key
+    ///
+    ///     let request = Request::new_from_response(&secret, &response)?
+    ///         .set_sitekey_uuid(sitekey);
+    /// # Ok(())
+    /// # }
+    /// # fn get_your_secret() -> String {
+    /// #   "0x123456789abcde0f123456789abcdef012345678".to_string()
+    /// # }
+    /// # fn get_response() -> String {
+    /// #    "thisisthelonglistofcharactersthatformsaresponse".to_string()
+    /// # }
+    /// ```
+    ///
+    /// #Logging
+    ///
+    /// If the `trace` feature is enabled a debug level span is created for the
+    /// method.
+    /// The secret field is not logged.
+    ///
+    #[cfg_attr(
+        feature = "trace",
+        tracing::instrument(
+            name = "Request verification from hcaptcha.",
+            skip(self, sitekey),
+            fields(captcha = ?self.captcha),
+            level = "debug"
+        )
+    )]
+    pub fn set_sitekey_uuid(mut self, sitekey: impl Into<Sitekey>) -> Self {
+        self.captcha.set_sitekey_uuid(sitekey);
+        self
+    }
+
+    /// Set the failure mode for the request
+    ///
+    /// Overrides the failure mode set on the [Client](crate::Client) for
+    /// this request.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), hcaptcha::Error> {
+    ///     use hcaptcha::{Captcha, FailureMode, Request};
+    ///
+    ///     let captcha = Captcha::new("thisisthelonglistofcharactersthatformsaresponse")?;
+    ///     let request = Request::new("0x123456789abcde0f123456789abcdef012345678", captcha)?
+    ///         .set_failure_mode(FailureMode::FailClosed);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_failure_mode(mut self, failure_mode: FailureMode) -> Self {
+        self.failure_mode = Some(failure_mode);
+        self
+    }
+
+    pub(crate) fn failure_mode(&self) -> Option<FailureMode> {
+        self.failure_mode
+    }
+
     #[allow(dead_code)]
     pub(crate) fn secret(&self) -> Secret {
         self.secret.clone()
@@ -339,6 +508,8 @@ mod tests {
     use claims::{assert_none, assert_ok};
     use rand::distr::Alphanumeric;
     use rand::{rng, RngExt};
+    use std::net::{IpAddr, Ipv4Addr};
+    use uuid::Uuid;
 
     fn random_hex_string(len: usize) -> String {
         let mut rng = rng();
@@ -397,4 +568,28 @@ mod tests {
         assert_none!(ip);
         assert_none!(key);
     }
+
+    #[test]
+    fn valid_new_from_secret() {
+        let secret: Secret = format!("0x{}", random_hex_string(40)).parse().unwrap();
+        let request = Request::new_from_secret(secret.clone(), dummy_captcha());
+
+        assert_eq!(request.secret(), secret);
+    }
+
+    #[test]
+    fn typed_remoteip_and_sitekey_are_set_on_captcha() {
+        let secret = format!("0x{}", random_hex_string(40));
+        let remoteip = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 10));
+        let sitekey = Uuid::new_v4();
+
+        let request = Request::new_from_response(&secret, &random_response())
+            .unwrap()
+            .set_remoteip_addr(remoteip)
+            .set_sitekey_uuid(sitekey);
+
+        let captcha = request.captcha();
+        assert_eq!(captcha.remoteip().unwrap().ip(), remoteip);
+        assert_eq!(captcha.sitekey().unwrap().to_string(), sitekey.to_string());
+    }
 }
@@ -88,13 +88,21 @@ pub struct Response {
     /// `enterprise` feature: a score denoting malicious activity.
     #[allow(dead_code)]
     #[cfg_attr(docsrs, doc(cfg(feature = "enterprise")))]
-    score: Option<Score>,
+    pub(crate) score: Option<Score>,
     /// `enterprise` feature: reason(s) for score. See [BotStop.com] for details
     ///
     /// [BotStop.com]: https://BotStop.com
     #[allow(dead_code)]
     #[cfg_attr(docsrs, doc(cfg(feature = "enterprise")))]
     score_reason: Option<HashSet<String>>,
+    /// true if the response was created by the client because the endpoint
+    /// could not be reached and the failure mode is fail-open.
+    #[serde(skip)]
+    degraded: bool,
+    /// `verification-cache` feature: signed pass issued by the client.
+    #[cfg(feature = "verification-cache")]
+    #[serde(skip)]
+    pub(crate) pass: Option<String>,
 }
 
 #[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
@@ -178,6 +186,16 @@ impl fmt::Display for Response {
 
 #[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
 impl Response {
+    /// Create the response returned in place of a verification when the
+    /// endpoint cannot be reached and the failure mode is fail-open.
+    pub(crate) fn new_degraded() -> Response {
+        Response {
+            success: true,
+            degraded: true,
+            ..Response::default()
+        }
+    }
+
     /// Check success of API call and return Error
     /// with the error codes if not successful.
     pub(crate) fn check_error(&self) -> Result<(), Error> {
@@ -195,6 +213,17 @@ impl Response {
         }
     }
 
+    /// Check if the response was created by the client because the
+    /// endpoint could not be reached
+    ///
+    /// A degraded response is only returned when the failure mode is
+    /// [FailureMode::FailOpen](crate::FailureMode::FailOpen). The token was
+    /// not verified, so a degraded response should only be accepted for
+    /// low risk actions.
+    pub fn degraded(&self) -> bool {
+        self.degraded
+    }
+
     /// Get the value of the success field
     ///
     /// # Example
@@ -409,6 +438,19 @@ impl Response {
         self.error_codes.clone()
     }
 
+    /// Get the signed pass issued for the verification
+    ///
+    /// The pass is only issued if a
+    /// [VerificationCache](crate::VerificationCache) has been set on the
+    /// [Client](crate::Client). Present the pass to
+    /// [Client::verify_pass](crate::Client::verify_pass) on later steps of
+    /// a flow instead of verifying a new token.
+    #[cfg(feature = "verification-cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "verification-cache")))]
+    pub fn pass(&self) -> Option<String> {
+        self.pass.clone()
+    }
+
     /// Get the value of the score field
     ///
     /// # Example
@@ -669,6 +711,9 @@ mod tests {
                 error_codes: Some(codes),
                 score: Some(0.9),
                 score_reason: Some(reasons),
+                degraded: false,
+                #[cfg(feature = "verification-cache")]
+                pass: None,
             };
 
             let formatted = format!("{response}");
@@ -698,6 +743,9 @@ mod tests {
                 error_codes: Some(codes),
                 score: None,
                 score_reason: None,
+                degraded: false,
+                #[cfg(feature = "verification-cache")]
+                pass: None,
             };
 
             let formatted = format!("{}", response);
@@ -719,6 +767,9 @@ mod tests {
             error_codes: None,
             score: None,
             score_reason: None,
+            degraded: false,
+            #[cfg(feature = "verification-cache")]
+            pass: None,
         };
 
         let formatted = format!("{response}");
@@ -739,6 +790,9 @@ mod tests {
             error_codes: None,
             score: None,
             score_reason: None,
+            degraded: false,
+            #[cfg(feature = "verification-cache")]
+            pass: None,
         };
         assert!(response.check_error().is_ok());
     }
@@ -755,6 +809,9 @@ mod tests {
             error_codes: Some(error_codes.clone()),
             score: None,
             score_reason: None,
+            degraded: false,
+            #[cfg(feature = "verification-cache")]
+            pass: None,
         };
         match response.check_error() {
             Err(Error::Codes(codes)) => {
@@ -774,6 +831,9 @@ mod tests {
             error_codes: None,
             score: None,
             score_reason: None,
+            degraded: false,
+            #[cfg(feature = "verification-cache")]
+            pass: None,
         };
 
         match response.check_error() {
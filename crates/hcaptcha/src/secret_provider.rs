@@ -0,0 +1,389 @@
+// SPDX-FileCopyrightText: 2022 jerusdp
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Providers to supply the secret to the [Client](crate::Client).
+//!
+//! A [SecretProvider] resolves the secret when a request is verified, so the
+//! secret does not need to be passed in with each request. Providers are
+//! included to read the secret from an environment variable or from a file
+//! (such as a Docker or Kubernetes secret mount). Implement the trait to
+//! fetch the secret from a vault or parameter store.
+//!
+//! Wrap a provider in a [CachedSecretProvider] to reuse the secret for a
+//! period of time rather than fetching it for every request.
+//!
+//! # Example
+//!
+//! ```no_run
+//!     use hcaptcha::{Captcha, Client, EnvSecretProvider};
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), hcaptcha::Error> {
+//! #   let token = "thisisthelonglistofcharactersthatformsaresponse";
+//!     let client = Client::new()
+//!         .set_secret_provider(EnvSecretProvider::new("HCAPTCHA_SECRET"));
+//!
+//!     let captcha = Captcha::new(token)?;
+//!     let response = client.verify_captcha(captcha).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! A provider for a secret store.
+//!
+//! ```no_run
+//!     use hcaptcha::{CachedSecretProvider, Client, Error, Secret, SecretProvider};
+//!     use std::time::Duration;
+//!
+//!     struct ParameterStore;
+//!
+//!     #[async_trait::async_trait]
+//!     impl SecretProvider for ParameterStore {
+//!         async fn secret(&self) -> Result<Secret, Error> {
+//!             let value = fetch_parameter("/hcaptcha/secret").await?;
+//!             value.parse()
+//!         }
+//!     }
+//!
+//!     let client = Client::new().set_secret_provider(CachedSecretProvider::new(
+//!         ParameterStore,
+//!         Duration::from_secs(300),
+//!     ));
+//! # async fn fetch_parameter(_key: &str) -> Result<String, Error> {
+//! #     Ok("0x123456789abcde0f123456789abcdef012345678".to_owned())
+//! # }
+//! ```
+
+use crate::{Error, Secret};
+use async_trait::async_trait;
+use std::fmt;
+use std::path::{Path, PathBuf};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::sync::Mutex;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::{Duration, Instant};
+
+/// Supply the secret used to verify a request.
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait SecretProvider: Send + Sync {
+    /// Return the secret.
+    ///
+    /// # Errors
+    ///
+    /// An [Error] is returned if the secret cannot be retrieved or is not
+    /// a valid [Secret].
+    async fn secret(&self) -> Result<Secret, Error>;
+}
+
+impl fmt::Debug for dyn SecretProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretProvider")
+    }
+}
+
+/// A fixed secret.
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl SecretProvider for Secret {
+    async fn secret(&self) -> Result<Secret, Error> {
+        Ok(self.clone())
+    }
+}
+
+/// Read the secret from an environment variable.
+#[derive(Debug, Clone)]
+pub struct EnvSecretProvider {
+    /// Name of the environment variable.
+    name: String,
+}
+
+impl EnvSecretProvider {
+    /// Create a provider to read the secret from the environment variable
+    /// `name`.
+    ///
+    /// # Example
+    /// ```
+    ///     use hcaptcha::EnvSecretProvider;
+    ///
+    ///     let provider = EnvSecretProvider::new("HCAPTCHA_SECRET");
+    /// ```
+    pub fn new(name: &str) -> Self {
+        EnvSecretProvider {
+            name: name.to_owned(),
+        }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl SecretProvider for EnvSecretProvider {
+    #[cfg_attr(
+        feature = "trace",
+        tracing::instrument(name = "Read secret from environment.", level = "debug")
+    )]
+    async fn secret(&self) -> Result<Secret, Error> {
+        let value = std::env::var(&self.name)?;
+        Secret::parse(value)
+    }
+}
+
+/// Read the secret from a file.
+///
+/// Leading and trailing whitespace, such as the trailing newline often
+/// found in a mounted secret, is removed.
+///
+/// The file is read on a separate thread so that the executor is not
+/// blocked, but it is still read for every verification. Wrap the provider
+/// in a [CachedSecretProvider] to read the file once per time to live.
+///
+/// # Example
+/// ```
+///     use hcaptcha::{CachedSecretProvider, Client, FileSecretProvider};
+///     use std::time::Duration;
+///
+///     let client = Client::new().set_secret_provider(CachedSecretProvider::new(
+///         FileSecretProvider::new("/run/secrets/hcaptcha_secret"),
+///         Duration::from_secs(60),
+///     ));
+/// ```
+#[derive(Debug, Clone)]
+pub struct FileSecretProvider {
+    /// Path to the file holding the secret.
+    path: PathBuf,
+}
+
+impl FileSecretProvider {
+    /// Create a provider to read the secret from the file at `path`.
+    ///
+    /// # Example
+    /// ```
+    ///     use hcaptcha::FileSecretProvider;
+    ///
+    ///     let provider = FileSecretProvider::new("/run/secrets/hcaptcha_secret");
+    /// ```
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        FileSecretProvider { path: path.into() }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl SecretProvider for FileSecretProvider {
+    #[cfg_attr(
+        feature = "trace",
+        tracing::instrument(name = "Read secret from file.", level = "debug")
+    )]
+    async fn secret(&self) -> Result<Secret, Error> {
+        let value = read_to_string(&self.path).await?;
+        Secret::parse(value.trim().to_owned())
+    }
+}
+
+/// Read the file at `path` on a separate thread.
+#[cfg(not(target_arch = "wasm32"))]
+async fn read_to_string(path: &Path) -> std::io::Result<String> {
+    let path = path.to_owned();
+    let (sender, receiver) = futures_channel::oneshot::channel();
+    std::thread::spawn(move || {
+        let _ = sender.send(std::fs::read_to_string(path));
+    });
+    receiver.await.map_err(std::io::Error::other)?
+}
+
+/// Read the file at `path`; wasm has no threads to read it on.
+#[cfg(target_arch = "wasm32")]
+async fn read_to_string(path: &Path) -> std::io::Result<String> {
+    std::fs::read_to_string(path)
+}
+
+/// Cache the secret from another provider for a fixed time to live.
+///
+/// The wrapped provider is only called when the cached secret has expired.
+/// If the provider fails the error is returned and the next call tries
+/// again.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+#[derive(Debug)]
+pub struct CachedSecretProvider<P> {
+    /// Provider to fetch the secret when the cache has expired.
+    provider: P,
+    /// How long the secret is cached.
+    ttl: Duration,
+    /// The cached secret and when it was fetched.
+    cache: Mutex<Option<(Secret, Instant)>>,
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl<P: SecretProvider> CachedSecretProvider<P> {
+    /// Cache the secret returned by `provider` for `ttl`.
+    ///
+    /// # Example
+    /// ```
+    ///     use hcaptcha::{CachedSecretProvider, FileSecretProvider};
+    ///     use std::time::Duration;
+    ///
+    ///     let provider = CachedSecretProvider::new(
+    ///         FileSecretProvider::new("/run/secrets/hcaptcha_secret"),
+    ///         Duration::from_secs(60),
+    ///     );
+    /// ```
+    pub fn new(provider: P, ttl: Duration) -> Self {
+        CachedSecretProvider {
+            provider,
+            ttl,
+            cache: Mutex::new(None),
+        }
+    }
+
+    /// Discard the cached secret so that the next call fetches it again.
+    pub fn invalidate(&self) {
+        *self.cache.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    fn cached(&self) -> Option<Secret> {
+        let cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        match &*cache {
+            Some((secret, fetched)) if fetched.elapsed() < self.ttl => Some(secret.clone()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<P: SecretProvider> SecretProvider for CachedSecretProvider<P> {
+    async fn secret(&self) -> Result<Secret, Error> {
+        if let Some(secret) = self.cached() {
+            return Ok(secret);
+        }
+        #[cfg(feature = "trace")]
+        tracing::debug!("Cached secret expired, fetching from provider");
+
+        let secret = self.provider.secret().await?;
+        *self.cache.lock().unwrap_or_else(|e| e.into_inner()) =
+            Some((secret.clone(), Instant::now()));
+        Ok(secret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Code;
+    use claims::{assert_err, assert_ok};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    const SECRET: &str = "0x123456789abcde0f123456789abcdef012345678";
+
+    struct CountingProvider(Arc<AtomicUsize>);
+
+    #[async_trait]
+    impl SecretProvider for CountingProvider {
+        async fn secret(&self) -> Result<Secret, Error> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            SECRET.parse()
+        }
+    }
+
+    #[tokio::test]
+    async fn env_provider_reads_secret() {
+        let name = "HCAPTCHA_TEST_ENV_PROVIDER_READS_SECRET";
+        std::env::set_var(name, SECRET);
+
+        let secret = EnvSecretProvider::new(name).secret().await;
+        assert_eq!(secret.unwrap().as_str(), SECRET);
+    }
+
+    #[tokio::test]
+    async fn env_provider_fails_if_variable_missing() {
+        let secret = EnvSecretProvider::new("HCAPTCHA_TEST_VARIABLE_IS_NOT_SET")
+            .secret()
+            .await;
+        assert!(matches!(secret, Err(Error::Env(_))));
+    }
+
+    #[tokio::test]
+    async fn env_provider_validates_secret() {
+        let name = "HCAPTCHA_TEST_ENV_PROVIDER_VALIDATES_SECRET";
+        std::env::set_var(name, "  ");
+
+        let secret = EnvSecretProvider::new(name).secret().await;
+        if let Err(Error::Codes(hs)) = secret {
+            assert!(hs.contains(&Code::MissingSecret));
+        } else {
+            panic!("Expected missing secret error");
+        }
+    }
+
+    #[tokio::test]
+    async fn file_provider_reads_secret_and_trims_newline() {
+        let path =
+            std::env::temp_dir().join(format!("hcaptcha-file-provider-{}", std::process::id()));
+        std::fs::write(&path, format!("{SECRET}\n")).unwrap();
+
+        let secret = FileSecretProvider::new(&path).secret().await;
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(secret.unwrap().as_str(), SECRET);
+    }
+
+    #[test]
+    fn file_provider_reads_without_a_runtime() {
+        let path = std::env::temp_dir().join(format!(
+            "hcaptcha-file-provider-executor-{}",
+            std::process::id()
+        ));
+        std::fs::write(&path, SECRET).unwrap();
+
+        let secret = futures::executor::block_on(FileSecretProvider::new(&path).secret());
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(secret.unwrap().as_str(), SECRET);
+    }
+
+    #[tokio::test]
+    async fn file_provider_fails_if_file_missing() {
+        let secret = FileSecretProvider::new("/this/file/does/not/exist")
+            .secret()
+            .await;
+        assert!(matches!(secret, Err(Error::Io(_))));
+    }
+
+    #[tokio::test]
+    async fn cached_provider_reuses_secret_within_ttl() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let provider =
+            CachedSecretProvider::new(CountingProvider(calls.clone()), Duration::from_secs(60));
+
+        assert_ok!(provider.secret().await);
+        assert_ok!(provider.secret().await);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        provider.invalidate();
+        assert_ok!(provider.secret().await);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn cached_provider_refreshes_expired_secret() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let provider = CachedSecretProvider::new(CountingProvider(calls.clone()), Duration::ZERO);
+
+        assert_ok!(provider.secret().await);
+        assert_ok!(provider.secret().await);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn cached_provider_does_not_cache_errors() {
+        let provider = CachedSecretProvider::new(
+            EnvSecretProvider::new("HCAPTCHA_TEST_VARIABLE_IS_NOT_SET"),
+            Duration::from_secs(60),
+        );
+
+        assert_err!(provider.secret().await);
+        assert!(provider.cached().is_none());
+    }
+}
@@ -0,0 +1,393 @@
+// SPDX-FileCopyrightText: 2022 jerusdp
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Registry of the secret and policy for each sitekey.
+//!
+//! A service that hosts forms for several hCaptcha accounts registers the
+//! secret for each sitekey in a [SiteRegistry]. The [Client](crate::Client)
+//! looks up the secret using the sitekey in the [Captcha](crate::Captcha)
+//! and checks the response against the [Policy] for the site.
+//!
+//! The registry can be loaded from a JSON file mapping each sitekey to its
+//! secret and policy. The file is checked for changes at most once per
+//! reload interval and read again when it is modified, so sites can be
+//! added or secrets rotated without restarting the service. Call
+//! [SiteRegistry::reload] to pick up a change straight away.
+//!
+//! ```json
+//! {
+//!     "10000000-ffff-ffff-ffff-000000000001": {
+//!         "secret": "0x0000000000000000000000000000000000000000",
+//!         "hostnames": ["example.com", "www.example.com"],
+//!         "max_score": 0.7
+//!     }
+//! }
+//! ```
+//!
+//! # Example
+//!
+//! ```no_run
+//!     use hcaptcha::{Captcha, Client, SiteRegistry};
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), hcaptcha::Error> {
+//! #   let token = "thisisthelonglistofcharactersthatformsaresponse";
+//!     let registry = SiteRegistry::from_file("/etc/hcaptcha/sites.json")?;
+//!     let client = Client::new().set_site_registry(registry);
+//!
+//!     let captcha = Captcha::new(token)?
+//!         .set_sitekey("10000000-ffff-ffff-ffff-000000000001")?;
+//!     let response = client.verify_captcha(captcha).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{Error, Policy, Secret, Sitekey};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Default minimum time between checks of the registry file for changes.
+const RELOAD_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The secret and policy for a site.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Site {
+    /// Secret for the hCaptcha account that owns the sitekey.
+    secret: Secret,
+    /// Checks made on the response for the site.
+    #[serde(flatten)]
+    policy: Policy,
+}
+
+impl Site {
+    /// Create a site with the secret and a policy that accepts every
+    /// successful response.
+    pub fn new(secret: Secret) -> Self {
+        Site {
+            secret,
+            policy: Policy::default(),
+        }
+    }
+
+    /// Set the policy for the site.
+    pub fn set_policy(mut self, policy: Policy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// The secret for the site.
+    pub fn secret(&self) -> &Secret {
+        &self.secret
+    }
+
+    /// The policy for the site.
+    pub fn policy(&self) -> &Policy {
+        &self.policy
+    }
+}
+
+/// File the registry was loaded from and when it was last modified.
+#[derive(Debug)]
+struct Source {
+    path: PathBuf,
+    /// Modification time of the file when it was last read, whether or not
+    /// it was valid.
+    modified: Mutex<Option<SystemTime>>,
+    /// Minimum time between checks of the file for changes.
+    reload_interval: Duration,
+    /// When the file was last checked for changes.
+    checked: Mutex<Option<Instant>>,
+}
+
+/// Map each sitekey to the secret and policy for the site.
+///
+/// # Example
+/// ```
+/// # fn main() -> Result<(), hcaptcha::Error> {
+///     use hcaptcha::{Client, Policy, Site, SiteRegistry};
+///
+///     let registry = SiteRegistry::new().add_site(
+///         "10000000-ffff-ffff-ffff-000000000001".parse()?,
+///         Site::new("0x0000000000000000000000000000000000000000".parse()?)
+///             .set_policy(Policy::new().add_hostname("example.com")),
+///     );
+///     let client = Client::new().set_site_registry(registry);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct SiteRegistry {
+    /// The registered sites.
+    sites: RwLock<HashMap<Sitekey, Site>>,
+    /// File to reload the sites from.
+    source: Option<Source>,
+}
+
+impl SiteRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        SiteRegistry::default()
+    }
+
+    /// Register the site for the sitekey, replacing any existing site.
+    pub fn add_site(self, sitekey: Sitekey, site: Site) -> Self {
+        self.sites
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(sitekey, site);
+        self
+    }
+
+    /// Load the registry from a JSON file.
+    ///
+    /// When a site is looked up the file is checked for changes, at most
+    /// once every five seconds, and read again if it has been modified. Use
+    /// [SiteRegistry::set_reload_interval] to change how often it is
+    /// checked.
+    ///
+    /// # Errors
+    ///
+    /// An [Error] is returned if the file cannot be read or any of the
+    /// sitekeys or secrets are not valid.
+    #[cfg_attr(
+        feature = "trace",
+        tracing::instrument(name = "Load site registry.", skip(path), level = "debug")
+    )]
+    pub fn from_file(path: impl Into<PathBuf>) -> Result<Self, Error> {
+        let registry = SiteRegistry {
+            sites: RwLock::default(),
+            source: Some(Source {
+                path: path.into(),
+                modified: Mutex::new(None),
+                reload_interval: RELOAD_INTERVAL,
+                checked: Mutex::new(None),
+            }),
+        };
+        registry.reload()?;
+        Ok(registry)
+    }
+
+    /// Set the minimum time between checks of the registry file for
+    /// changes.
+    ///
+    /// A registry that was not loaded from a file is not changed.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # fn main() -> Result<(), hcaptcha::Error> {
+    ///     use hcaptcha::SiteRegistry;
+    ///     use std::time::Duration;
+    ///
+    ///     let registry = SiteRegistry::from_file("/etc/hcaptcha/sites.json")?
+    ///         .set_reload_interval(Duration::from_secs(30));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_reload_interval(mut self, interval: Duration) -> Self {
+        if let Some(source) = &mut self.source {
+            source.reload_interval = interval;
+        }
+        self
+    }
+
+    /// Read the registry file again.
+    ///
+    /// If the file cannot be read or is not valid the error is returned and
+    /// the registered sites are unchanged. The file is not read again until
+    /// it is modified. A registry that was not loaded from a file is not
+    /// changed.
+    #[cfg_attr(
+        feature = "trace",
+        tracing::instrument(name = "Reload site registry.", skip(self), level = "debug")
+    )]
+    pub fn reload(&self) -> Result<(), Error> {
+        let Some(source) = &self.source else {
+            return Ok(());
+        };
+        // Record the modification time before parsing so that an invalid
+        // file is not read again on every lookup.
+        let modified = std::fs::metadata(&source.path)?.modified().ok();
+        *source.modified.lock().unwrap_or_else(|e| e.into_inner()) = modified;
+        let contents = std::fs::read_to_string(&source.path)?;
+        let sites: HashMap<Sitekey, Site> = serde_json::from_str(&contents)?;
+
+        #[cfg(feature = "trace")]
+        tracing::debug!("Loaded {} sites", sites.len());
+        *self.sites.write().unwrap_or_else(|e| e.into_inner()) = sites;
+        Ok(())
+    }
+
+    /// Look up the site for the sitekey.
+    ///
+    /// If the registry was loaded from a file and the reload interval has
+    /// passed since the file was last checked, the file is read again first
+    /// if it has been modified. If the reload fails the sites already
+    /// registered continue to be used.
+    pub fn site(&self, sitekey: &Sitekey) -> Option<Site> {
+        if self.is_check_due() && self.is_modified() {
+            if let Err(_e) = self.reload() {
+                #[cfg(feature = "trace")]
+                tracing::warn!("Failed to reload site registry: {}", _e);
+            }
+        }
+        self.sites
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(sitekey)
+            .cloned()
+    }
+
+    /// Check if the reload interval has passed since the file was last
+    /// checked, and if so start a new interval.
+    fn is_check_due(&self) -> bool {
+        let Some(source) = &self.source else {
+            return false;
+        };
+        let mut checked = source.checked.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        if checked.is_some_and(|checked| now.duration_since(checked) < source.reload_interval) {
+            return false;
+        }
+        *checked = Some(now);
+        true
+    }
+
+    /// Check if the registry file has been modified since it was loaded.
+    fn is_modified(&self) -> bool {
+        let Some(source) = &self.source else {
+            return false;
+        };
+        let modified = std::fs::metadata(&source.path)
+            .and_then(|m| m.modified())
+            .ok();
+        modified.is_some() && modified != *source.modified.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Code;
+    use claims::{assert_err, assert_none, assert_ok, assert_some};
+
+    const SITEKEY: &str = "10000000-ffff-ffff-ffff-000000000001";
+    const SECRET: &str = "0x123456789abcde0f123456789abcdef012345678";
+    const OTHER_SECRET: &str = "0xabcdef0123456789abcdef0123456789abcdef01";
+
+    fn registry_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("hcaptcha-{name}-{}.json", std::process::id()))
+    }
+
+    fn registry_json(secret: &str) -> String {
+        format!(r#"{{ "{SITEKEY}": {{ "secret": "{secret}", "hostnames": ["example.com"] }} }}"#)
+    }
+
+    #[test]
+    fn site_is_found_by_sitekey() {
+        let registry = SiteRegistry::new()
+            .add_site(SITEKEY.parse().unwrap(), Site::new(SECRET.parse().unwrap()));
+
+        let site = assert_some!(registry.site(&SITEKEY.parse().unwrap()));
+        assert_eq!(site.secret().as_str(), SECRET);
+        assert_none!(registry.site(&"20000000-ffff-ffff-ffff-000000000002".parse().unwrap()));
+    }
+
+    #[test]
+    fn registry_is_loaded_from_file() {
+        let path = registry_path("registry-load");
+        std::fs::write(&path, registry_json(SECRET)).unwrap();
+
+        let registry = SiteRegistry::from_file(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let site = assert_some!(assert_ok!(registry).site(&SITEKEY.parse().unwrap()));
+        assert_eq!(site.secret().as_str(), SECRET);
+        assert_eq!(site.policy(), &Policy::new().add_hostname("example.com"));
+    }
+
+    #[test]
+    fn invalid_secret_in_file_is_rejected() {
+        let path = registry_path("registry-invalid");
+        std::fs::write(&path, registry_json("")).unwrap();
+
+        let registry = SiteRegistry::from_file(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let error = assert_err!(registry);
+        assert!(error.to_string().contains(Code::MissingSecret.as_str()));
+    }
+
+    #[test]
+    fn modified_file_is_reloaded() {
+        let path = registry_path("registry-reload");
+        std::fs::write(&path, registry_json(SECRET)).unwrap();
+        let registry = SiteRegistry::from_file(&path)
+            .unwrap()
+            .set_reload_interval(Duration::ZERO);
+
+        write_modified(&path, &registry_json(OTHER_SECRET));
+
+        let site = registry.site(&SITEKEY.parse().unwrap());
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(site.unwrap().secret().as_str(), OTHER_SECRET);
+    }
+
+    #[test]
+    fn modified_file_is_not_checked_within_reload_interval() {
+        let path = registry_path("registry-interval");
+        std::fs::write(&path, registry_json(SECRET)).unwrap();
+        let registry = SiteRegistry::from_file(&path).unwrap();
+        // The first lookup checks the file and starts the interval.
+        assert_some!(registry.site(&SITEKEY.parse().unwrap()));
+
+        write_modified(&path, &registry_json(OTHER_SECRET));
+
+        let site = registry.site(&SITEKEY.parse().unwrap());
+        assert_ok!(registry.reload());
+        let reloaded = registry.site(&SITEKEY.parse().unwrap());
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(site.unwrap().secret().as_str(), SECRET);
+        assert_eq!(reloaded.unwrap().secret().as_str(), OTHER_SECRET);
+    }
+
+    #[test]
+    fn failed_reload_is_not_retried_until_modified() {
+        let path = registry_path("registry-not-retried");
+        std::fs::write(&path, registry_json(SECRET)).unwrap();
+        let registry = SiteRegistry::from_file(&path)
+            .unwrap()
+            .set_reload_interval(Duration::ZERO);
+
+        write_modified(&path, "not json");
+        let site = registry.site(&SITEKEY.parse().unwrap());
+        let retry = registry.is_modified();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(site.unwrap().secret().as_str(), SECRET);
+        assert!(!retry);
+    }
+
+    /// Write the file with a modification time clearly after the last load.
+    fn write_modified(path: &PathBuf, contents: &str) {
+        std::fs::write(path, contents).unwrap();
+        let file = std::fs::File::options().write(true).open(path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(10))
+            .unwrap();
+    }
+
+    #[test]
+    fn failed_reload_keeps_sites() {
+        let path = registry_path("registry-failed-reload");
+        std::fs::write(&path, registry_json(SECRET)).unwrap();
+        let registry = SiteRegistry::from_file(&path).unwrap();
+
+        std::fs::write(&path, "not json").unwrap();
+        assert_err!(registry.reload());
+
+        let site = registry.site(&SITEKEY.parse().unwrap());
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(site.unwrap().secret().as_str(), SECRET);
+    }
+}
@@ -0,0 +1,392 @@
+// SPDX-FileCopyrightText: 2022 jerusdp
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # Timer
+//!
+//! The [Client](crate::Client) waits with a [Timer] when a timeout applies,
+//! such as the queue timeout of the concurrency limit. The [ThreadTimer] is
+//! used by default as it runs under any executor. With the `tokio` feature
+//! the `TokioTimer` sleeps on the tokio time driver instead, and on wasi
+//! targets, which have no threads, it is the default timer.
+//!
+//! # Example
+//!
+//! ```
+//!     use hcaptcha::{Client, ThreadTimer};
+//!     use std::time::Duration;
+//!
+//!     let client = Client::new()
+//!         .set_concurrency_limit(50, Duration::from_millis(500))
+//!         .set_timer(ThreadTimer);
+//! ```
+
+use async_trait::async_trait;
+#[cfg(not(target_arch = "wasm32"))]
+use std::cmp::Reverse;
+#[cfg(not(target_arch = "wasm32"))]
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt;
+use std::future::{poll_fn, Future};
+use std::pin::pin;
+#[cfg(not(target_arch = "wasm32"))]
+use std::pin::Pin;
+use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::{Condvar, Mutex, MutexGuard, OnceLock};
+use std::task::Poll;
+#[cfg(not(target_arch = "wasm32"))]
+use std::task::{Context, Waker};
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+/// Wait for a duration on the executor running the client.
+///
+/// # Example
+///
+/// ```
+///     use hcaptcha::{Client, Timer};
+///     use std::time::Duration;
+///
+///     #[derive(Debug)]
+///     struct TokioSleep;
+///
+///     #[async_trait::async_trait]
+///     impl Timer for TokioSleep {
+///         async fn sleep(&self, duration: Duration) {
+///             tokio::time::sleep(duration).await
+///         }
+///     }
+///
+///     let client = Client::new().set_timer(TokioSleep);
+/// ```
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait Timer: fmt::Debug + Send + Sync {
+    /// Complete once `duration` has passed.
+    async fn sleep(&self, duration: Duration);
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<T: Timer + ?Sized> Timer for Arc<T> {
+    async fn sleep(&self, duration: Duration) {
+        (**self).sleep(duration).await
+    }
+}
+
+/// [Timer] that sleeps with the tokio time driver.
+///
+/// It must be used within a tokio runtime with the time driver enabled.
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioTimer;
+
+#[cfg(feature = "tokio")]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl Timer for TokioTimer {
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await
+    }
+}
+
+/// [Timer] that sleeps on a timer thread shared by all sleeps.
+///
+/// This is the default timer. It does not depend on an executor, so it can
+/// be used with any async runtime. A single thread, started on first use, wakes each sleep
+/// at its deadline. A sleep that is dropped before its deadline, such as
+/// the timeout of a request that completed, is removed from the thread.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThreadTimer;
+
+#[cfg(not(target_arch = "wasm32"))]
+#[async_trait]
+impl Timer for ThreadTimer {
+    async fn sleep(&self, duration: Duration) {
+        static TIMER_THREAD: OnceLock<Arc<TimerThread>> = OnceLock::new();
+        let timer_thread = TIMER_THREAD.get_or_init(TimerThread::start);
+        Sleep::new(timer_thread, duration).await
+    }
+}
+
+/// The deadlines and wakers of the pending sleeps.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Default)]
+struct Sleeps {
+    /// Deadline of each sleep, earliest first. Entries of dropped sleeps are
+    /// skipped when they are reached.
+    deadlines: BinaryHeap<Reverse<(Instant, u64)>>,
+    /// Waker of each pending sleep.
+    wakers: HashMap<u64, Waker>,
+    /// Identifier for the next sleep.
+    next_id: u64,
+}
+
+/// Thread that wakes each sleep at its deadline.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Default)]
+struct TimerThread {
+    sleeps: Mutex<Sleeps>,
+    /// Notified when a sleep with an earlier deadline is added.
+    changed: Condvar,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl TimerThread {
+    /// Start the thread to wake the sleeps.
+    fn start() -> Arc<TimerThread> {
+        let timer_thread = Arc::new(TimerThread::default());
+        let shared = Arc::clone(&timer_thread);
+        std::thread::Builder::new()
+            .name("hcaptcha-timer".to_owned())
+            .spawn(move || shared.run())
+            .expect("failed to start the timer thread");
+        timer_thread
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Sleeps> {
+        self.sleeps.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Wake each sleep whose deadline has passed, then wait for the next.
+    fn run(&self) {
+        let mut sleeps = self.lock();
+        loop {
+            let now = Instant::now();
+            let mut due = Vec::new();
+            while let Some(&Reverse((deadline, id))) = sleeps.deadlines.peek() {
+                if deadline > now {
+                    break;
+                }
+                sleeps.deadlines.pop();
+                due.extend(sleeps.wakers.remove(&id));
+            }
+            let next = sleeps
+                .deadlines
+                .peek()
+                .map(|Reverse((deadline, _))| *deadline);
+            drop(sleeps);
+            due.into_iter().for_each(Waker::wake);
+
+            sleeps = self.lock();
+            sleeps = match next {
+                Some(deadline) => {
+                    let timeout = deadline.saturating_duration_since(Instant::now());
+                    self.changed
+                        .wait_timeout(sleeps, timeout)
+                        .unwrap_or_else(|e| e.into_inner())
+                        .0
+                }
+                None => self.changed.wait(sleeps).unwrap_or_else(|e| e.into_inner()),
+            };
+        }
+    }
+
+    /// Register the waker for the sleep, adding the sleep if it is new.
+    fn register(&self, id: Option<u64>, deadline: Instant, waker: &Waker) -> u64 {
+        let mut sleeps = self.lock();
+        if let Some(id) = id {
+            match sleeps.wakers.get_mut(&id) {
+                Some(registered) if registered.will_wake(waker) => {}
+                Some(registered) => registered.clone_from(waker),
+                None => {
+                    sleeps.wakers.insert(id, waker.clone());
+                }
+            }
+            return id;
+        }
+        let id = sleeps.next_id;
+        sleeps.next_id += 1;
+        let earliest = sleeps
+            .deadlines
+            .peek()
+            .is_none_or(|Reverse((next, _))| deadline < *next);
+        sleeps.deadlines.push(Reverse((deadline, id)));
+        sleeps.wakers.insert(id, waker.clone());
+        if earliest {
+            self.changed.notify_one();
+        }
+        id
+    }
+
+    /// Remove the sleep. The deadline is dropped from the heap as well once
+    /// most of the entries belong to removed sleeps.
+    fn remove(&self, id: u64) {
+        let mut sleeps = self.lock();
+        sleeps.wakers.remove(&id);
+        if sleeps.deadlines.len() > 2 * sleeps.wakers.len() + 64 {
+            let Sleeps {
+                deadlines, wakers, ..
+            } = &mut *sleeps;
+            deadlines.retain(|Reverse((_, id))| wakers.contains_key(id));
+        }
+    }
+}
+
+/// Future that completes at a deadline, woken by the [TimerThread].
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug)]
+struct Sleep<'a> {
+    timer_thread: &'a TimerThread,
+    deadline: Instant,
+    /// Identifier of the sleep once it is registered with the thread.
+    id: Option<u64>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<'a> Sleep<'a> {
+    fn new(timer_thread: &'a TimerThread, duration: Duration) -> Self {
+        Sleep {
+            timer_thread,
+            deadline: Instant::now() + duration,
+            id: None,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Future for Sleep<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if Instant::now() >= self.deadline {
+            if let Some(id) = self.id.take() {
+                self.timer_thread.remove(id);
+            }
+            return Poll::Ready(());
+        }
+        let id = self
+            .timer_thread
+            .register(self.id, self.deadline, cx.waker());
+        self.id = Some(id);
+        Poll::Pending
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Drop for Sleep<'_> {
+    fn drop(&mut self) {
+        if let Some(id) = self.id.take() {
+            self.timer_thread.remove(id);
+        }
+    }
+}
+
+/// The timer used unless one is set with
+/// [Client::set_timer](crate::Client::set_timer).
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn default_timer() -> Option<Arc<dyn Timer>> {
+    Some(Arc::new(ThreadTimer))
+}
+
+/// The timer used unless one is set with
+/// [Client::set_timer](crate::Client::set_timer).
+#[cfg(all(target_arch = "wasm32", feature = "tokio"))]
+pub(crate) fn default_timer() -> Option<Arc<dyn Timer>> {
+    Some(Arc::new(TokioTimer))
+}
+
+/// Without threads or tokio there is no timer unless one is set with
+/// [Client::set_timer](crate::Client::set_timer).
+#[cfg(all(target_arch = "wasm32", not(feature = "tokio")))]
+pub(crate) fn default_timer() -> Option<Arc<dyn Timer>> {
+    None
+}
+
+/// Await `future` unless `duration` passes first on the `timer`.
+///
+/// Returns `None` if the duration passed before the future completed.
+pub(crate) async fn timeout<F: Future>(
+    timer: &dyn Timer,
+    duration: Duration,
+    future: F,
+) -> Option<F::Output> {
+    let mut future = pin!(future);
+    let mut sleep = timer.sleep(duration);
+    poll_fn(|cx| {
+        if let Poll::Ready(output) = future.as_mut().poll(cx) {
+            return Poll::Ready(Some(output));
+        }
+        sleep.as_mut().poll(cx).map(|()| None)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::future::pending;
+
+    #[test]
+    fn thread_timer_timeout_elapses_under_futures_executor() {
+        let result = futures::executor::block_on(timeout(
+            &ThreadTimer,
+            Duration::from_millis(10),
+            pending::<()>(),
+        ));
+
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn thread_timer_timeout_completes_under_smol() {
+        let result = smol::block_on(timeout(&ThreadTimer, Duration::from_secs(1), async { 42 }));
+
+        assert_eq!(result, Some(42));
+    }
+
+    #[test]
+    fn dropped_sleep_is_removed_from_the_timer_thread() {
+        let timer_thread = TimerThread::start();
+
+        let result = futures::executor::block_on(timeout_on(
+            &timer_thread,
+            Duration::from_secs(60),
+            async { 42 },
+        ));
+        assert_eq!(result, Some(42));
+        assert!(timer_thread.lock().wakers.is_empty());
+    }
+
+    #[test]
+    fn sleeps_share_the_timer_thread() {
+        let timer_thread = TimerThread::start();
+        let sleeps = (1..=20u64)
+            .rev()
+            .map(|ms| Sleep::new(&timer_thread, Duration::from_millis(ms)));
+
+        futures::executor::block_on(futures::future::join_all(sleeps));
+
+        assert!(timer_thread.lock().wakers.is_empty());
+    }
+
+    /// Race the future against a sleep on `timer_thread`.
+    async fn timeout_on<F: Future>(
+        timer_thread: &TimerThread,
+        duration: Duration,
+        future: F,
+    ) -> Option<F::Output> {
+        let mut future = pin!(future);
+        let mut sleep = pin!(Sleep::new(timer_thread, duration));
+        poll_fn(|cx| {
+            if sleep.as_mut().poll(cx).is_ready() {
+                return Poll::Ready(None);
+            }
+            future.as_mut().poll(cx).map(Some)
+        })
+        .await
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn tokio_timer_timeout_elapses() {
+        let timer: Arc<dyn Timer> = Arc::new(TokioTimer);
+
+        let result = timeout(&timer, Duration::from_millis(10), pending::<()>()).await;
+
+        assert_eq!(result, None);
+    }
+}
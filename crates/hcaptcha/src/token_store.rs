@@ -0,0 +1,289 @@
+// SPDX-FileCopyrightText: 2022 jerusdp
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Stores to record the tokens that have been verified.
+//!
+//! The Hcaptcha API rejects a token that has already been verified with
+//! [Code::InvalidAlreadySeen](crate::Code::InvalidAlreadySeen), but only
+//! after a call to the API. A [TokenStore] set on the
+//! [Client](crate::Client) records each token before it is verified and
+//! the client rejects a token found in the store without calling the API.
+//! A token that fails verification is removed again.
+//!
+//! A [MemoryTokenStore] is included for a single process. Implement the
+//! trait over a shared cache, such as Redis with `SET NX`, to detect tokens
+//! replayed to another instance of a service.
+//!
+//! # Example
+//!
+//! ```no_run
+//!     use hcaptcha::{Captcha, Client, MemoryTokenStore, Request};
+//!     use std::time::Duration;
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), hcaptcha::Error> {
+//! #   let token = "thisisthelonglistofcharactersthatformsaresponse";
+//! #   let secret = "0x123456789abcde0f123456789abcdef012345678";
+//!     let client = Client::new()
+//!         .set_token_store(MemoryTokenStore::new(Duration::from_secs(300)));
+//!
+//!     let request = Request::new(secret, Captcha::new(token)?)?;
+//!     let response = client.verify_request(request).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{ClientResponse, Error};
+use async_trait::async_trait;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::collections::HashMap;
+use std::fmt;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::sync::Mutex;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::{Duration, Instant};
+
+/// Record the tokens that have been verified.
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait TokenStore: Send + Sync {
+    /// Record the token unless it has already been recorded.
+    ///
+    /// Returns `true` if the token was recorded and `false` if it was
+    /// already in the store. The check and the insert must be atomic, so
+    /// that of two concurrent verifications of a token only one records it.
+    ///
+    /// # Errors
+    ///
+    /// An [Error] is returned if the store cannot be updated.
+    async fn insert_if_absent(&self, token: &ClientResponse) -> Result<bool, Error>;
+
+    /// Remove the token so that it can be verified again.
+    ///
+    /// # Errors
+    ///
+    /// An [Error] is returned if the store cannot be updated.
+    async fn remove(&self, token: &ClientResponse) -> Result<(), Error>;
+}
+
+impl fmt::Debug for dyn TokenStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("TokenStore")
+    }
+}
+
+/// Number of tokens recorded before the size of the store triggers a sweep.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+const MIN_SWEEP_LEN: usize = 1024;
+
+/// Record the verified tokens in memory for a fixed time to live.
+///
+/// Expired tokens are removed as new tokens are recorded, once per time to
+/// live or when the number of tokens has doubled since the last sweep. The
+/// time to live should be at least as long as a token remains valid with
+/// the Hcaptcha API.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+#[derive(Debug)]
+pub struct MemoryTokenStore {
+    /// How long a token is remembered.
+    ttl: Duration,
+    /// The recorded tokens.
+    tokens: Mutex<Tokens>,
+}
+
+/// The recorded tokens and when expired tokens were last removed.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+#[derive(Debug)]
+struct Tokens {
+    /// The recorded tokens and when they were recorded.
+    recorded: HashMap<String, Instant>,
+    /// When expired tokens were last removed.
+    swept: Option<Instant>,
+    /// Number of tokens that triggers the next sweep.
+    sweep_len: usize,
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl Tokens {
+    /// Remove the expired tokens if the time to live has passed since the
+    /// last sweep or the store has grown past the sweep length.
+    fn sweep(&mut self, ttl: Duration, now: Instant) {
+        let due = self
+            .swept
+            .is_none_or(|swept| now.duration_since(swept) >= ttl);
+        if !due && self.recorded.len() < self.sweep_len {
+            return;
+        }
+        self.recorded
+            .retain(|_, recorded| now.duration_since(*recorded) < ttl);
+        self.swept = Some(now);
+        self.sweep_len = (self.recorded.len() * 2).max(MIN_SWEEP_LEN);
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl MemoryTokenStore {
+    /// Remember each verified token for `ttl`.
+    ///
+    /// # Example
+    /// ```
+    ///     use hcaptcha::MemoryTokenStore;
+    ///     use std::time::Duration;
+    ///
+    ///     let store = MemoryTokenStore::new(Duration::from_secs(300));
+    /// ```
+    pub fn new(ttl: Duration) -> Self {
+        MemoryTokenStore {
+            ttl,
+            tokens: Mutex::new(Tokens {
+                recorded: HashMap::new(),
+                swept: None,
+                sweep_len: MIN_SWEEP_LEN,
+            }),
+        }
+    }
+
+    /// The number of tokens remembered, including any that have expired
+    /// but not yet been removed.
+    pub fn len(&self) -> usize {
+        self.tokens
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .recorded
+            .len()
+    }
+
+    /// Check if no tokens are remembered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl TokenStore for MemoryTokenStore {
+    async fn insert_if_absent(&self, token: &ClientResponse) -> Result<bool, Error> {
+        let now = Instant::now();
+        let mut tokens = self.tokens.lock().unwrap_or_else(|e| e.into_inner());
+        tokens.sweep(self.ttl, now);
+        let seen = tokens
+            .recorded
+            .get(token.as_str())
+            .is_some_and(|recorded| now.duration_since(*recorded) < self.ttl);
+        if !seen {
+            tokens.recorded.insert(token.as_str().to_owned(), now);
+        }
+        Ok(!seen)
+    }
+
+    async fn remove(&self, token: &ClientResponse) -> Result<(), Error> {
+        let mut tokens = self.tokens.lock().unwrap_or_else(|e| e.into_inner());
+        tokens.recorded.remove(token.as_str());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(s: &str) -> ClientResponse {
+        s.parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn recorded_token_is_not_inserted_again() {
+        let store = MemoryTokenStore::new(Duration::from_secs(60));
+
+        assert!(store.insert_if_absent(&token("first")).await.unwrap());
+        assert!(!store.insert_if_absent(&token("first")).await.unwrap());
+        assert!(store.insert_if_absent(&token("second")).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn removed_token_can_be_inserted_again() {
+        let store = MemoryTokenStore::new(Duration::from_secs(60));
+
+        assert!(store.insert_if_absent(&token("first")).await.unwrap());
+        store.remove(&token("first")).await.unwrap();
+        assert!(store.is_empty());
+        assert!(store.insert_if_absent(&token("first")).await.unwrap());
+    }
+
+    #[test]
+    fn concurrent_inserts_record_the_token_once() {
+        let store = MemoryTokenStore::new(Duration::from_secs(60));
+
+        let inserted = std::thread::scope(|scope| {
+            let inserts: Vec<_> = (0..8)
+                .map(|_| {
+                    scope.spawn(|| {
+                        futures::executor::block_on(store.insert_if_absent(&token("first")))
+                            .unwrap()
+                    })
+                })
+                .collect();
+            inserts
+                .into_iter()
+                .map(|insert| insert.join().unwrap())
+                .filter(|inserted| *inserted)
+                .count()
+        });
+
+        assert_eq!(inserted, 1);
+    }
+
+    #[tokio::test]
+    async fn expired_token_is_inserted_again_and_is_removed() {
+        let store = MemoryTokenStore::new(Duration::ZERO);
+
+        assert!(store.insert_if_absent(&token("first")).await.unwrap());
+        assert!(store.insert_if_absent(&token("first")).await.unwrap());
+
+        store.insert_if_absent(&token("second")).await.unwrap();
+        assert_eq!(store.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn expired_tokens_are_not_swept_within_ttl() {
+        let store = MemoryTokenStore::new(Duration::from_millis(50));
+
+        store.insert_if_absent(&token("first")).await.unwrap();
+        store.insert_if_absent(&token("second")).await.unwrap();
+        assert_eq!(store.len(), 2);
+
+        std::thread::sleep(Duration::from_millis(60));
+        store.insert_if_absent(&token("third")).await.unwrap();
+        assert_eq!(store.len(), 1);
+    }
+
+    #[test]
+    fn sweep_is_triggered_by_size() {
+        let ttl = Duration::from_secs(60);
+        let start = Instant::now();
+        let mut tokens = Tokens {
+            recorded: HashMap::new(),
+            swept: Some(start),
+            sweep_len: MIN_SWEEP_LEN,
+        };
+        for i in 0..MIN_SWEEP_LEN {
+            tokens.recorded.insert(i.to_string(), start);
+        }
+        let later = start + Duration::from_secs(30);
+        tokens
+            .recorded
+            .insert("recent".to_owned(), later + Duration::from_secs(15));
+
+        // Nothing has expired yet, so the store keeps growing until twice
+        // the size left by the sweep.
+        tokens.sweep(ttl, later);
+        assert_eq!(tokens.recorded.len(), MIN_SWEEP_LEN + 1);
+        assert_eq!(tokens.sweep_len, 2 * (MIN_SWEEP_LEN + 1));
+
+        // Once the time to live has passed since that sweep the expired
+        // tokens are removed.
+        tokens.sweep(ttl, later + ttl);
+        assert_eq!(tokens.recorded.len(), 1);
+    }
+}
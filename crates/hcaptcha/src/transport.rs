@@ -0,0 +1,285 @@
+// SPDX-FileCopyrightText: 2022 jerusdp
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! # HTTP Transport
+//!
+//! The [Client](crate::Client) posts the form to the siteverify endpoint
+//! with a [Transport]. The transports are available behind features:
+//!
+//! * `reqwest` - `ReqwestTransport` sends with a reqwest client. This is
+//!   the default transport.
+//! * `hyper` - `HyperTransport` sends with a hyper client.
+//! * `ureq` - `UreqTransport` sends with a ureq agent. It is the default
+//!   transport if the `reqwest` feature is not enabled.
+//! * `wasi-http` - `WasiHttpTransport` sends with the `wasi:http` interface
+//!   on wasi targets, where it is the default transport.
+//!
+//! If none of the default transports is enabled the client has no
+//! transport until one is set with [Client::set_transport](crate::Client::set_transport).
+//!
+//! Another HTTP stack can be used by implementing [Transport].
+//!
+//! # Example
+//!
+//! ```
+//!     use hcaptcha::{Client, ReqwestTransport};
+//!
+//!     let client = Client::new()
+//!         .set_transport(ReqwestTransport::new(reqwest::Client::new()));
+//! ```
+
+use crate::Error;
+use async_trait::async_trait;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use url::Url;
+
+#[cfg(all(feature = "hyper", not(target_arch = "wasm32")))]
+mod hyper_transport;
+#[cfg(all(feature = "reqwest", not(target_os = "wasi")))]
+mod reqwest_transport;
+#[cfg(all(feature = "ureq", not(target_arch = "wasm32")))]
+mod ureq_transport;
+#[cfg(all(feature = "wasi-http", target_os = "wasi"))]
+mod wasi_http_transport;
+
+#[cfg(all(feature = "hyper", not(target_arch = "wasm32")))]
+pub use hyper_transport::HyperTransport;
+#[cfg(all(feature = "reqwest", not(target_os = "wasi")))]
+pub use reqwest_transport::ReqwestTransport;
+#[cfg(all(feature = "ureq", not(target_arch = "wasm32")))]
+pub use ureq_transport::UreqTransport;
+#[cfg(all(feature = "wasi-http", target_os = "wasi"))]
+pub use wasi_http_transport::WasiHttpTransport;
+
+/// Content type of the form posted to the endpoint.
+#[cfg_attr(
+    not(any(
+        all(feature = "reqwest", not(target_os = "wasi")),
+        all(any(feature = "hyper", feature = "ureq"), not(target_arch = "wasm32")),
+        all(feature = "wasi-http", target_os = "wasi")
+    )),
+    allow(dead_code)
+)]
+pub(crate) const FORM_CONTENT_TYPE: &str = "application/x-www-form-urlencoded";
+
+/// The transport used unless one is set with
+/// [Client::set_transport](crate::Client::set_transport).
+#[cfg(all(feature = "reqwest", not(target_os = "wasi")))]
+pub(crate) fn default_transport() -> Arc<dyn Transport> {
+    Arc::new(ReqwestTransport::default())
+}
+
+/// The transport used unless one is set with
+/// [Client::set_transport](crate::Client::set_transport).
+#[cfg(all(
+    feature = "ureq",
+    not(feature = "reqwest"),
+    not(target_arch = "wasm32")
+))]
+pub(crate) fn default_transport() -> Arc<dyn Transport> {
+    Arc::new(UreqTransport::new())
+}
+
+/// The transport used unless one is set with
+/// [Client::set_transport](crate::Client::set_transport).
+#[cfg(all(feature = "wasi-http", target_os = "wasi"))]
+pub(crate) fn default_transport() -> Arc<dyn Transport> {
+    Arc::new(WasiHttpTransport::new())
+}
+
+/// The transport used unless one is set with
+/// [Client::set_transport](crate::Client::set_transport).
+#[cfg(not(any(
+    all(feature = "reqwest", not(target_os = "wasi")),
+    all(feature = "ureq", not(target_arch = "wasm32")),
+    all(feature = "wasi-http", target_os = "wasi")
+)))]
+pub(crate) fn default_transport() -> Arc<dyn Transport> {
+    Arc::new(MissingTransport)
+}
+
+/// Transport of a client built without any of the default transports.
+///
+/// Each request fails until a transport is set.
+#[cfg(not(any(
+    all(feature = "reqwest", not(target_os = "wasi")),
+    all(feature = "ureq", not(target_arch = "wasm32")),
+    all(feature = "wasi-http", target_os = "wasi")
+)))]
+#[derive(Debug)]
+struct MissingTransport;
+
+#[cfg(not(any(
+    all(feature = "reqwest", not(target_os = "wasi")),
+    all(feature = "ureq", not(target_arch = "wasm32")),
+    all(feature = "wasi-http", target_os = "wasi")
+)))]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl Transport for MissingTransport {
+    async fn post_form(&self, _url: &Url, _form: String) -> Result<TransportResponse, Error> {
+        Err(Error::Transport(
+            "no HTTP transport: enable the `reqwest`, `ureq` or `wasi-http` feature, \
+             or set a transport with `Client::set_transport`"
+                .into(),
+        ))
+    }
+}
+
+/// Send the verification form to the siteverify endpoint.
+///
+/// # Example
+///
+/// ```
+///     use hcaptcha::{Error, Transport, TransportResponse};
+///     use url::Url;
+///
+///     #[derive(Debug)]
+///     struct Offline;
+///
+///     #[async_trait::async_trait]
+///     impl Transport for Offline {
+///         async fn post_form(&self, _url: &Url, _form: String) -> Result<TransportResponse, Error> {
+///             Ok(TransportResponse::new(200, br#"{"success":true}"#.to_vec()))
+///         }
+///     }
+///
+///     let client = hcaptcha::Client::new().set_transport(Offline);
+/// ```
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait Transport: fmt::Debug + Send + Sync {
+    /// Post the url encoded `form` to `url` and read the response.
+    ///
+    /// The form is sent with the `application/x-www-form-urlencoded`
+    /// content type. Any HTTP status is returned in the response; the
+    /// client decides which statuses are errors.
+    ///
+    /// # Errors
+    ///
+    /// An [Error] is returned if the request cannot be sent or the response
+    /// cannot be read. Return [Error::Connect] when no connection could be
+    /// made and [Error::Timeout] when the endpoint did not respond in time:
+    /// the client fails over and the failure mode applies only to these
+    /// errors and to unavailable statuses.
+    async fn post_form(&self, url: &Url, form: String) -> Result<TransportResponse, Error>;
+
+    /// Post the url encoded `form` to `url` like [Transport::post_form],
+    /// giving up if the connection is not established within
+    /// `connect_timeout`.
+    ///
+    /// The client uses this to hedge requests that are slow to connect, see
+    /// [Client::set_hedge_delay](crate::Client::set_hedge_delay). The
+    /// default implementation ignores the timeout and calls
+    /// [Transport::post_form], so requests are not hedged.
+    ///
+    /// # Errors
+    ///
+    /// If the connection timed out a connect error such as [Error::Connect]
+    /// is returned and none of the form has been sent. Other errors are
+    /// returned as for [Transport::post_form].
+    async fn post_form_with_connect_timeout(
+        &self,
+        url: &Url,
+        form: String,
+        connect_timeout: Duration,
+    ) -> Result<TransportResponse, Error> {
+        let _ = connect_timeout;
+        self.post_form(url, form).await
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<T: Transport + ?Sized> Transport for Arc<T> {
+    async fn post_form(&self, url: &Url, form: String) -> Result<TransportResponse, Error> {
+        (**self).post_form(url, form).await
+    }
+
+    async fn post_form_with_connect_timeout(
+        &self,
+        url: &Url,
+        form: String,
+        connect_timeout: Duration,
+    ) -> Result<TransportResponse, Error> {
+        (**self)
+            .post_form_with_connect_timeout(url, form, connect_timeout)
+            .await
+    }
+}
+
+/// The status and body of the response read by a [Transport].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransportResponse {
+    status: u16,
+    body: Vec<u8>,
+}
+
+impl TransportResponse {
+    /// Create a response from the HTTP status code and the body.
+    pub fn new(status: u16, body: Vec<u8>) -> Self {
+        TransportResponse { status, body }
+    }
+
+    /// The HTTP status code.
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    /// The body of the response.
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// Check if the status is a success.
+    pub(crate) fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Check if the status shows the endpoint is unavailable for now: a
+    /// server error or `429 Too Many Requests`.
+    pub(crate) fn is_unavailable(&self) -> bool {
+        self.status == 429 || (500..600).contains(&self.status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(any(
+        all(feature = "reqwest", not(target_os = "wasi")),
+        all(feature = "ureq", not(target_arch = "wasm32")),
+        all(feature = "wasi-http", target_os = "wasi")
+    )))]
+    #[test]
+    fn missing_transport_fails_each_request() {
+        let url = Url::parse("https://api.hcaptcha.com/siteverify").unwrap();
+
+        let result =
+            futures::executor::block_on(default_transport().post_form(&url, String::new()));
+
+        assert!(matches!(result, Err(Error::Transport(_))));
+    }
+
+    #[test]
+    fn success_statuses() {
+        assert!(TransportResponse::new(200, Vec::new()).is_success());
+        assert!(!TransportResponse::new(302, Vec::new()).is_success());
+        assert!(!TransportResponse::new(404, Vec::new()).is_success());
+        assert!(!TransportResponse::new(500, Vec::new()).is_success());
+    }
+
+    #[test]
+    fn unavailable_statuses() {
+        assert!(!TransportResponse::new(200, Vec::new()).is_unavailable());
+        assert!(!TransportResponse::new(400, Vec::new()).is_unavailable());
+        assert!(!TransportResponse::new(404, Vec::new()).is_unavailable());
+        assert!(TransportResponse::new(429, Vec::new()).is_unavailable());
+        assert!(TransportResponse::new(500, Vec::new()).is_unavailable());
+        assert!(TransportResponse::new(503, Vec::new()).is_unavailable());
+    }
+}
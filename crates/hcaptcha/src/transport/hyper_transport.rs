@@ -0,0 +1,129 @@
+// SPDX-FileCopyrightText: 2022 jerusdp
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use super::{Transport, TransportResponse, FORM_CONTENT_TYPE};
+use crate::Error;
+use async_trait::async_trait;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::header::CONTENT_TYPE;
+use hyper_util::client::legacy::connect::Connect;
+use hyper_util::client::legacy::Client;
+use std::fmt;
+use url::Url;
+
+/// [Transport] that sends with a hyper client.
+///
+/// The connector of the client decides whether https is supported; hyper
+/// itself provides no TLS. A failure to connect is returned as
+/// [Error::Connect] and other errors as [Error::Transport].
+///
+/// The connect timeout used to hedge requests is not applied; set one on
+/// the connector of the client to fail over from endpoints that are slow to
+/// connect.
+///
+/// # Example
+///
+/// ```
+///     use hcaptcha::HyperTransport;
+///     use hyper_util::client::legacy::Client;
+///     use hyper_util::rt::TokioExecutor;
+///
+///     let http = Client::builder(TokioExecutor::new()).build_http();
+///     let client = hcaptcha::Client::new().set_transport(HyperTransport::new(http));
+/// ```
+#[derive(Clone)]
+pub struct HyperTransport<C> {
+    client: Client<C, Full<Bytes>>,
+}
+
+impl<C> HyperTransport<C> {
+    /// Create a transport that sends with `client`.
+    pub fn new(client: Client<C, Full<Bytes>>) -> Self {
+        HyperTransport { client }
+    }
+}
+
+impl<C> From<Client<C, Full<Bytes>>> for HyperTransport<C> {
+    fn from(client: Client<C, Full<Bytes>>) -> Self {
+        HyperTransport::new(client)
+    }
+}
+
+impl<C> fmt::Debug for HyperTransport<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HyperTransport").finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl<C> Transport for HyperTransport<C>
+where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    async fn post_form(&self, url: &Url, form: String) -> Result<TransportResponse, Error> {
+        let request = hyper::Request::post(url.as_str())
+            .header(CONTENT_TYPE, FORM_CONTENT_TYPE)
+            .body(Full::new(Bytes::from(form)))
+            .map_err(|e| Error::Transport(Box::new(e)))?;
+        let response = self.client.request(request).await.map_err(|e| {
+            if e.is_connect() {
+                Error::Connect(Box::new(e))
+            } else {
+                Error::Transport(Box::new(e))
+            }
+        })?;
+        let status = response.status().as_u16();
+        let body = response
+            .into_body()
+            .collect()
+            .await
+            .map_err(|e| Error::Transport(Box::new(e)))?
+            .to_bytes();
+        Ok(TransportResponse::new(status, body.to_vec()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper_util::rt::TokioExecutor;
+    use wiremock::matchers::{body_string, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn hyper_transport_posts_the_form() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .and(header("content-type", FORM_CONTENT_TYPE))
+            .and(body_string("response=token&secret=secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"success":true}"#))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let url = Url::parse(&format!("{}/siteverify", mock_server.uri())).unwrap();
+
+        let transport = HyperTransport::new(Client::builder(TokioExecutor::new()).build_http());
+        let response = transport
+            .post_form(&url, "response=token&secret=secret".to_owned())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.body(), br#"{"success":true}"#);
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn hyper_transport_connection_error_is_connect_error() {
+        let url = Url::parse("http://127.0.0.1:1/siteverify").unwrap();
+        let transport = HyperTransport::new(Client::builder(TokioExecutor::new()).build_http());
+
+        let result = transport.post_form(&url, String::new()).await;
+
+        assert!(matches!(result, Err(Error::Connect(_))));
+    }
+}
@@ -0,0 +1,127 @@
+// SPDX-FileCopyrightText: 2022 jerusdp
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use super::{Transport, TransportResponse, FORM_CONTENT_TYPE};
+use crate::Error;
+use async_trait::async_trait;
+use reqwest::header::CONTENT_TYPE;
+#[cfg(not(target_arch = "wasm32"))]
+use std::collections::HashMap;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::{Arc, Mutex};
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Duration;
+use url::Url;
+
+/// [Transport] that sends with a reqwest client.
+///
+/// This is the default transport. Errors are returned as [Error::Reqwest].
+///
+/// The default transport applies the connect timeout used to hedge requests
+/// with a client built for that timeout. A transport created with
+/// [ReqwestTransport::new] sends every request with the given client and
+/// ignores the timeout; set a
+/// [connect timeout](reqwest::ClientBuilder::connect_timeout) on the client
+/// to fail over from endpoints that are slow to connect instead.
+///
+/// # Example
+///
+/// ```
+///     use hcaptcha::{Client, ReqwestTransport};
+///     use std::time::Duration;
+///
+///     let http = reqwest::Client::builder()
+///         .timeout(Duration::from_secs(5))
+///         .build()
+///         .unwrap();
+///     let client = Client::new().set_transport(ReqwestTransport::new(http));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+    /// Default clients with a connect timeout, by timeout, if requests are
+    /// hedged.
+    #[cfg(not(target_arch = "wasm32"))]
+    connect_timeout_clients: Option<Arc<Mutex<HashMap<Duration, reqwest::Client>>>>,
+}
+
+impl Default for ReqwestTransport {
+    fn default() -> Self {
+        ReqwestTransport {
+            client: reqwest::Client::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            connect_timeout_clients: Some(Arc::default()),
+        }
+    }
+}
+
+impl ReqwestTransport {
+    /// Create a transport that sends with `client`.
+    pub fn new(client: reqwest::Client) -> Self {
+        ReqwestTransport {
+            client,
+            #[cfg(not(target_arch = "wasm32"))]
+            connect_timeout_clients: None,
+        }
+    }
+
+    /// The client to send with when connecting must complete within
+    /// `connect_timeout`.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn connect_timeout_client(&self, connect_timeout: Duration) -> Result<reqwest::Client, Error> {
+        let Some(clients) = &self.connect_timeout_clients else {
+            return Ok(self.client.clone());
+        };
+        let mut clients = clients.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(client) = clients.get(&connect_timeout) {
+            return Ok(client.clone());
+        }
+        let client = reqwest::Client::builder()
+            .connect_timeout(connect_timeout)
+            .build()?;
+        clients.insert(connect_timeout, client.clone());
+        Ok(client)
+    }
+}
+
+impl From<reqwest::Client> for ReqwestTransport {
+    fn from(client: reqwest::Client) -> Self {
+        ReqwestTransport::new(client)
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl Transport for ReqwestTransport {
+    async fn post_form(&self, url: &Url, form: String) -> Result<TransportResponse, Error> {
+        send(&self.client, url, form).await
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn post_form_with_connect_timeout(
+        &self,
+        url: &Url,
+        form: String,
+        connect_timeout: Duration,
+    ) -> Result<TransportResponse, Error> {
+        send(&self.connect_timeout_client(connect_timeout)?, url, form).await
+    }
+}
+
+/// Post the form with `client` and read the response.
+async fn send(
+    client: &reqwest::Client,
+    url: &Url,
+    form: String,
+) -> Result<TransportResponse, Error> {
+    let response = client
+        .post(url.clone())
+        .header(CONTENT_TYPE, FORM_CONTENT_TYPE)
+        .body(form)
+        .send()
+        .await?;
+    let status = response.status().as_u16();
+    let body = response.bytes().await?;
+    Ok(TransportResponse::new(status, body.to_vec()))
+}
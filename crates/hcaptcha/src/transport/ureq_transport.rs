@@ -0,0 +1,258 @@
+// SPDX-FileCopyrightText: 2022 jerusdp
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use super::{Transport, TransportResponse, FORM_CONTENT_TYPE};
+use crate::Error;
+use async_trait::async_trait;
+use futures_channel::oneshot;
+use std::time::Duration;
+use ureq::Agent;
+use url::Url;
+
+/// [Transport] that sends with a ureq agent.
+///
+/// ureq is a blocking client with a small dependency tree. Each request is
+/// sent on a thread of its own so the async runtime is not blocked, and the
+/// thread lives until the response has been read. Under load a thread is
+/// started for every request in flight, so limit the requests in flight
+/// with [Client::set_concurrency_limit](crate::Client::set_concurrency_limit)
+/// to bound the number of threads. A failure to connect is returned as
+/// [Error::Connect], a timeout as [Error::Timeout] and other errors as
+/// [Error::Transport].
+///
+/// # Example
+///
+/// ```
+///     use hcaptcha::{Client, UreqTransport};
+///
+///     let client = Client::new().set_transport(UreqTransport::new());
+/// ```
+#[derive(Debug, Clone)]
+pub struct UreqTransport {
+    agent: Agent,
+}
+
+impl Default for UreqTransport {
+    fn default() -> Self {
+        UreqTransport::new()
+    }
+}
+
+impl UreqTransport {
+    /// Create a transport with a default agent.
+    pub fn new() -> Self {
+        let config = Agent::config_builder().http_status_as_error(false).build();
+        UreqTransport {
+            agent: Agent::new_with_config(config),
+        }
+    }
+}
+
+impl From<Agent> for UreqTransport {
+    /// Create a transport that sends with `agent`.
+    ///
+    /// HTTP error statuses returned as errors by the agent are passed on to
+    /// the client without a body.
+    fn from(agent: Agent) -> Self {
+        UreqTransport { agent }
+    }
+}
+
+#[async_trait]
+impl Transport for UreqTransport {
+    async fn post_form(&self, url: &Url, form: String) -> Result<TransportResponse, Error> {
+        self.spawn(url, form, None).await
+    }
+
+    async fn post_form_with_connect_timeout(
+        &self,
+        url: &Url,
+        form: String,
+        connect_timeout: Duration,
+    ) -> Result<TransportResponse, Error> {
+        self.spawn(url, form, Some(connect_timeout)).await
+    }
+}
+
+impl UreqTransport {
+    /// Send the request on a thread of its own and wait for the response.
+    async fn spawn(
+        &self,
+        url: &Url,
+        form: String,
+        connect_timeout: Option<Duration>,
+    ) -> Result<TransportResponse, Error> {
+        let agent = self.agent.clone();
+        let url = url.to_string();
+        let (sender, receiver) = oneshot::channel();
+        std::thread::spawn(move || {
+            let _ = sender.send(send(&agent, &url, form, connect_timeout));
+        });
+        receiver.await.map_err(|e| Error::Transport(Box::new(e)))?
+    }
+}
+
+/// Post the form and read the response, blocking the thread.
+///
+/// The connect timeout of the agent is replaced by `connect_timeout` if
+/// there is one.
+fn send(
+    agent: &Agent,
+    url: &str,
+    form: String,
+    connect_timeout: Option<Duration>,
+) -> Result<TransportResponse, Error> {
+    let mut request = agent.post(url);
+    if connect_timeout.is_some() {
+        request = request.config().timeout_connect(connect_timeout).build();
+    }
+    let response = request.header("content-type", FORM_CONTENT_TYPE).send(form);
+    let mut response = match response {
+        Ok(response) => response,
+        Err(ureq::Error::StatusCode(status)) => {
+            return Ok(TransportResponse::new(status, Vec::new()))
+        }
+        Err(e) => return Err(transport_error(e)),
+    };
+    let status = response.status().as_u16();
+    let body = response.body_mut().read_to_vec().map_err(transport_error)?;
+    Ok(TransportResponse::new(status, body))
+}
+
+/// Classify a ureq error by whether the connection was made.
+fn transport_error(error: ureq::Error) -> Error {
+    use std::io::ErrorKind;
+    use ureq::Timeout;
+
+    match &error {
+        ureq::Error::HostNotFound
+        | ureq::Error::ConnectionFailed
+        | ureq::Error::Timeout(Timeout::Resolve | Timeout::Connect) => {
+            Error::Connect(Box::new(error))
+        }
+        ureq::Error::Io(e)
+            if matches!(
+                e.kind(),
+                ErrorKind::ConnectionRefused
+                    | ErrorKind::HostUnreachable
+                    | ErrorKind::NetworkUnreachable
+                    | ErrorKind::AddrNotAvailable
+            ) =>
+        {
+            Error::Connect(Box::new(error))
+        }
+        ureq::Error::Timeout(_) => Error::Timeout(Box::new(error)),
+        ureq::Error::Io(e) if e.kind() == ErrorKind::TimedOut => Error::Timeout(Box::new(error)),
+        _ => Error::Transport(Box::new(error)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_string, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn ureq_transport_posts_the_form() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .and(header("content-type", FORM_CONTENT_TYPE))
+            .and(body_string("response=token&secret=secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"success":true}"#))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let url = Url::parse(&format!("{}/siteverify", mock_server.uri())).unwrap();
+
+        let response = UreqTransport::new()
+            .post_form(&url, "response=token&secret=secret".to_owned())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.body(), br#"{"success":true}"#);
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn ureq_transport_returns_server_error_status() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&mock_server)
+            .await;
+        let url = Url::parse(&format!("{}/siteverify", mock_server.uri())).unwrap();
+
+        let response = UreqTransport::new()
+            .post_form(&url, String::new())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), 503);
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn ureq_transport_connection_refused_is_connect_error() {
+        let url = Url::parse("http://127.0.0.1:1/siteverify").unwrap();
+
+        let result = UreqTransport::new().post_form(&url, String::new()).await;
+
+        assert!(matches!(result, Err(Error::Connect(_))));
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[tokio::test]
+    async fn ureq_transport_gives_up_connecting_after_the_timeout() {
+        // Accepts connections but never completes the TLS handshake.
+        let stalled = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!(
+            "https://{}/siteverify",
+            stalled.local_addr().unwrap()
+        ))
+        .unwrap();
+
+        let result = UreqTransport::new()
+            .post_form_with_connect_timeout(&url, String::new(), Duration::from_millis(100))
+            .await;
+
+        assert!(matches!(result, Err(Error::Connect(_))));
+    }
+
+    #[cfg_attr(miri, ignore)]
+    #[test]
+    fn client_verifies_with_ureq_transport_under_smol() {
+        // The mock server runs on its own runtime; the client does not.
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let mock_server = runtime.block_on(async {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .and(path("/siteverify"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"success":true}"#))
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+            mock_server
+        });
+        let client = crate::Client::new_with(&format!("{}/siteverify", mock_server.uri()))
+            .unwrap()
+            .set_transport(UreqTransport::new())
+            .set_timer(crate::ThreadTimer)
+            .set_concurrency_limit(1, std::time::Duration::from_secs(5));
+        let captcha = crate::Captcha::new("response_token").unwrap();
+        let request =
+            crate::Request::new("0x123456789abcde0f123456789abcdef012345678", captcha).unwrap();
+
+        let response = smol::block_on(client.verify_request(request));
+
+        assert!(response.unwrap().success());
+        runtime.block_on(mock_server.verify());
+    }
+}
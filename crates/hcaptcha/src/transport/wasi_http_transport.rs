@@ -0,0 +1,175 @@
+// SPDX-FileCopyrightText: 2022 jerusdp
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use super::{Transport, TransportResponse, FORM_CONTENT_TYPE};
+use crate::Error;
+use async_trait::async_trait;
+use std::time::Duration;
+use url::Url;
+use wasi::http::outgoing_handler;
+use wasi::http::types::{
+    ErrorCode, Fields, Method, OutgoingBody, OutgoingRequest, RequestOptions, Scheme,
+};
+use wasi::io::streams::StreamError;
+
+/// Maximum number of bytes written to or read from a stream at a time.
+const CHUNK_SIZE: usize = 4096;
+
+/// [Transport] that sends with the `wasi:http` outgoing handler.
+///
+/// This is the default transport on wasi targets, where reqwest is not
+/// available. The request is made with the blocking calls of the interface.
+/// DNS and connection failures are returned as [Error::Connect], timeouts as
+/// [Error::Timeout] and other errors as [Error::Transport].
+///
+/// # Example
+///
+/// ```no_run
+///     use hcaptcha::{Client, WasiHttpTransport};
+///
+///     let client = Client::new().set_transport(WasiHttpTransport::new());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct WasiHttpTransport;
+
+impl WasiHttpTransport {
+    /// Create a transport for the `wasi:http` outgoing handler of the host.
+    pub fn new() -> Self {
+        WasiHttpTransport
+    }
+}
+
+#[async_trait(?Send)]
+impl Transport for WasiHttpTransport {
+    async fn post_form(&self, url: &Url, form: String) -> Result<TransportResponse, Error> {
+        send(url, form, None)
+    }
+
+    async fn post_form_with_connect_timeout(
+        &self,
+        url: &Url,
+        form: String,
+        connect_timeout: Duration,
+    ) -> Result<TransportResponse, Error> {
+        send(url, form, Some(connect_timeout))
+    }
+}
+
+/// Post the form and read the response, giving up if the connection is not
+/// established within `connect_timeout` if there is one.
+fn send(
+    url: &Url,
+    form: String,
+    connect_timeout: Option<Duration>,
+) -> Result<TransportResponse, Error> {
+    let headers = Fields::from_list(&[(
+        "content-type".to_owned(),
+        FORM_CONTENT_TYPE.as_bytes().to_vec(),
+    )])
+    .map_err(|e| Error::Transport(Box::new(e)))?;
+    let request = OutgoingRequest::new(headers);
+    request
+        .set_method(&Method::Post)
+        .map_err(|()| invalid("method"))?;
+    let scheme = match url.scheme() {
+        "http" => Scheme::Http,
+        "https" => Scheme::Https,
+        other => Scheme::Other(other.to_owned()),
+    };
+    request
+        .set_scheme(Some(&scheme))
+        .map_err(|()| invalid("scheme"))?;
+    let authority = match (url.host_str(), url.port()) {
+        (Some(host), Some(port)) => format!("{host}:{port}"),
+        (Some(host), None) => host.to_owned(),
+        (None, _) => return Err(invalid("authority")),
+    };
+    request
+        .set_authority(Some(&authority))
+        .map_err(|()| invalid("authority"))?;
+    let path = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_owned(),
+    };
+    request
+        .set_path_with_query(Some(&path))
+        .map_err(|()| invalid("path"))?;
+
+    let options = match connect_timeout {
+        Some(timeout) => {
+            let options = RequestOptions::new();
+            let nanos = u64::try_from(timeout.as_nanos()).unwrap_or(u64::MAX);
+            options
+                .set_connect_timeout(Some(nanos))
+                .map_err(|()| invalid("connect timeout"))?;
+            Some(options)
+        }
+        None => None,
+    };
+
+    let body = request.body().map_err(|()| invalid("body"))?;
+    let future =
+        outgoing_handler::handle(request, options).map_err(|e| Error::Transport(Box::new(e)))?;
+    {
+        let stream = body.write().map_err(|()| invalid("body"))?;
+        for chunk in form.as_bytes().chunks(CHUNK_SIZE) {
+            stream
+                .blocking_write_and_flush(chunk)
+                .map_err(stream_error)?;
+        }
+    }
+    OutgoingBody::finish(body, None).map_err(|e| Error::Transport(Box::new(e)))?;
+
+    future.subscribe().block();
+    let response = match future.get() {
+        Some(Ok(Ok(response))) => response,
+        Some(Ok(Err(e))) => return Err(response_error(e)),
+        Some(Err(())) | None => return Err(invalid("response")),
+    };
+    let status = response.status();
+    let incoming = response.consume().map_err(|()| invalid("response body"))?;
+    let mut body = Vec::new();
+    {
+        let stream = incoming.stream().map_err(|()| invalid("response body"))?;
+        loop {
+            match stream.blocking_read(CHUNK_SIZE as u64) {
+                Ok(chunk) => body.extend_from_slice(&chunk),
+                Err(StreamError::Closed) => break,
+                Err(e) => return Err(stream_error(e)),
+            }
+        }
+    }
+    Ok(TransportResponse::new(status, body))
+}
+
+/// Classify the error code of a failed request by whether the connection
+/// was made.
+fn response_error(code: ErrorCode) -> Error {
+    match code {
+        ErrorCode::DnsTimeout
+        | ErrorCode::DnsError(_)
+        | ErrorCode::DestinationNotFound
+        | ErrorCode::DestinationUnavailable
+        | ErrorCode::DestinationIpUnroutable
+        | ErrorCode::ConnectionRefused
+        | ErrorCode::ConnectionTimeout => Error::Connect(Box::new(code)),
+        ErrorCode::ConnectionReadTimeout
+        | ErrorCode::ConnectionWriteTimeout
+        | ErrorCode::HttpResponseTimeout => Error::Timeout(Box::new(code)),
+        code => Error::Transport(Box::new(code)),
+    }
+}
+
+/// Error for a part of the request or response the host rejected.
+fn invalid(part: &str) -> Error {
+    Error::Transport(format!("wasi:http rejected the {part}").into())
+}
+
+/// Error for a failed read or write on a body stream.
+fn stream_error(error: StreamError) -> Error {
+    match error {
+        StreamError::LastOperationFailed(e) => Error::Transport(e.to_debug_string().into()),
+        StreamError::Closed => Error::Transport("wasi:http body stream closed".into()),
+    }
+}
@@ -0,0 +1,315 @@
+// SPDX-FileCopyrightText: 2022 jerusdp
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Signed passes to show that a captcha was verified recently.
+//!
+//! A flow with several steps, such as a checkout, may need to check that the
+//! user passed a captcha at each step. A token can only be verified once, so
+//! after the first successful verification the [Client](crate::Client)
+//! issues a pass, returned by [Response::pass](crate::Response::pass), that
+//! the following steps present instead of a new token.
+//!
+//! The pass records the hostname, sitekey, remote ip and time of the
+//! verification and is signed with HMAC-SHA256. It is verified offline by
+//! the [VerificationCache] that issued it until its time to live expires.
+//!
+//! Requires the `verification-cache` feature.
+//!
+//! # Example
+//!
+//! ```no_run
+//!     use hcaptcha::{Captcha, Client, Request, VerificationCache};
+//!     use std::time::Duration;
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), hcaptcha::Error> {
+//! #   let token = "thisisthelonglistofcharactersthatformsaresponse";
+//! #   let secret = "0x123456789abcde0f123456789abcdef012345678";
+//! #   let key = b"a key of at least thirty two bytes";
+//!     let client = Client::new()
+//!         .set_verification_cache(VerificationCache::new(key, Duration::from_secs(600)));
+//!
+//!     let captcha = Captcha::new(token)?.set_remoteip("192.0.2.10")?;
+//!     let response = client.verify_request(Request::new(secret, captcha)?).await?;
+//!     let pass = response.pass().unwrap_or_default();
+//!
+//!     // On a later step of the flow
+//!     let verified = client.verify_pass(&pass, Some("192.0.2.10".parse()?))?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{Captcha, Code, Error, Remoteip, Response, Sitekey};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::collections::HashSet;
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// The details of a verification recorded in a pass.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct VerificationPass {
+    /// Hostname of the site where the captcha was solved.
+    #[serde(rename = "h", default)]
+    hostname: Option<String>,
+    /// Sitekey of the captcha.
+    #[serde(rename = "s", default)]
+    sitekey: Option<Sitekey>,
+    /// Ip address of the client that solved the captcha.
+    #[serde(rename = "i", default)]
+    remoteip: Option<Remoteip>,
+    /// Time of the verification in seconds since the unix epoch.
+    #[serde(rename = "t")]
+    issued_at: u64,
+}
+
+impl VerificationPass {
+    /// Hostname of the site where the captcha was solved.
+    pub fn hostname(&self) -> Option<&str> {
+        self.hostname.as_deref()
+    }
+
+    /// Sitekey of the captcha.
+    pub fn sitekey(&self) -> Option<&Sitekey> {
+        self.sitekey.as_ref()
+    }
+
+    /// Ip address of the client that solved the captcha.
+    pub fn remoteip(&self) -> Option<Remoteip> {
+        self.remoteip
+    }
+
+    /// Time of the verification.
+    pub fn issued_at(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.issued_at)
+    }
+}
+
+/// Issue and verify signed passes for successful verifications.
+///
+/// Every instance of a service that verifies the passes must use the same
+/// key. The key should be at least 32 random bytes and kept secret.
+#[derive(Clone)]
+pub struct VerificationCache {
+    /// Key to sign the passes.
+    key: Vec<u8>,
+    /// How long a pass is valid.
+    ttl: Duration,
+}
+
+impl fmt::Debug for VerificationCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VerificationCache")
+            .field("ttl", &self.ttl)
+            .finish_non_exhaustive()
+    }
+}
+
+impl VerificationCache {
+    /// Sign passes with `key` that are valid for `ttl`.
+    ///
+    /// # Example
+    /// ```
+    ///     use hcaptcha::VerificationCache;
+    ///     use std::time::Duration;
+    ///
+    ///     let cache = VerificationCache::new(b"a key of at least thirty two bytes", Duration::from_secs(600));
+    /// ```
+    pub fn new(key: &[u8], ttl: Duration) -> Self {
+        VerificationCache {
+            key: key.to_vec(),
+            ttl,
+        }
+    }
+
+    /// Issue a pass for the successful verification of the captcha.
+    ///
+    /// # Errors
+    ///
+    /// An [Error] is returned if the pass cannot be serialized.
+    #[cfg_attr(
+        feature = "trace",
+        tracing::instrument(name = "Issue verification pass.", skip_all, level = "debug")
+    )]
+    pub fn issue(&self, captcha: &Captcha, response: &Response) -> Result<String, Error> {
+        let pass = VerificationPass {
+            hostname: response.hostname(),
+            sitekey: captcha.sitekey.clone(),
+            remoteip: captcha.remoteip,
+            issued_at: unix_time(SystemTime::now()),
+        };
+        self.sign(&pass)
+    }
+
+    /// Verify the signature and age of the pass.
+    ///
+    /// If the pass records a remote ip the `remoteip` of the current request
+    /// must be the same.
+    ///
+    /// # Errors
+    ///
+    /// [Code::ExpiredPass] is returned if the pass is older than the time to
+    /// live and [Code::InvalidPass] if it is not a valid pass, was not signed
+    /// with the key or was issued to a different remote ip.
+    #[cfg_attr(
+        feature = "trace",
+        tracing::instrument(name = "Verify verification pass.", skip(self, pass), level = "debug")
+    )]
+    pub fn verify(
+        &self,
+        pass: &str,
+        remoteip: Option<Remoteip>,
+    ) -> Result<VerificationPass, Error> {
+        self.verify_at(pass, remoteip, SystemTime::now())
+    }
+
+    fn sign(&self, pass: &VerificationPass) -> Result<String, Error> {
+        let payload = serde_json::to_vec(pass)?;
+        let signature = self.mac(&payload).finalize().into_bytes();
+        Ok(format!(
+            "{}.{}",
+            hex::encode(payload),
+            hex::encode(signature)
+        ))
+    }
+
+    fn verify_at(
+        &self,
+        pass: &str,
+        remoteip: Option<Remoteip>,
+        now: SystemTime,
+    ) -> Result<VerificationPass, Error> {
+        let (payload, signature) = pass
+            .split_once('.')
+            .ok_or_else(|| error(Code::InvalidPass))?;
+        let payload = hex::decode(payload).map_err(|_| error(Code::InvalidPass))?;
+        let signature = hex::decode(signature).map_err(|_| error(Code::InvalidPass))?;
+        self.mac(&payload)
+            .verify_slice(&signature)
+            .map_err(|_| error(Code::InvalidPass))?;
+
+        let pass: VerificationPass =
+            serde_json::from_slice(&payload).map_err(|_| error(Code::InvalidPass))?;
+        if unix_time(now).saturating_sub(pass.issued_at) > self.ttl.as_secs() {
+            #[cfg(feature = "trace")]
+            tracing::debug!("Verification pass has expired");
+            return Err(error(Code::ExpiredPass));
+        }
+        if pass.remoteip.is_some() && pass.remoteip != remoteip {
+            #[cfg(feature = "trace")]
+            tracing::debug!("Verification pass was issued to another remote ip");
+            return Err(error(Code::InvalidPass));
+        }
+        Ok(pass)
+    }
+
+    fn mac(&self, payload: &[u8]) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC accepts any key length");
+        mac.update(payload);
+        mac
+    }
+}
+
+fn unix_time(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn error(code: Code) -> Error {
+    let mut codes = HashSet::new();
+    codes.insert(code);
+    Error::Codes(codes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::{assert_err, assert_ok};
+
+    const KEY: &[u8] = b"0123456789abcdef0123456789abcdef";
+
+    fn cache() -> VerificationCache {
+        VerificationCache::new(KEY, Duration::from_secs(600))
+    }
+
+    fn captcha() -> Captcha {
+        Captcha::new("response_token")
+            .unwrap()
+            .set_remoteip("192.0.2.10")
+            .unwrap()
+            .set_sitekey("10000000-ffff-ffff-ffff-000000000001")
+            .unwrap()
+    }
+
+    fn response() -> Response {
+        serde_json::from_str(r#"{ "success": true, "hostname": "example.com" }"#).unwrap()
+    }
+
+    fn codes(result: Result<VerificationPass, Error>) -> HashSet<Code> {
+        match result {
+            Err(Error::Codes(codes)) => codes,
+            _ => panic!("Expected error codes"),
+        }
+    }
+
+    #[test]
+    fn issued_pass_is_verified() {
+        let pass = cache().issue(&captcha(), &response()).unwrap();
+
+        let verified = assert_ok!(cache().verify(&pass, Some("192.0.2.10".parse().unwrap())));
+        assert_eq!(verified.hostname(), Some("example.com"));
+        assert_eq!(
+            verified.sitekey().map(|s| s.as_str()),
+            Some("10000000-ffff-ffff-ffff-000000000001")
+        );
+    }
+
+    #[test]
+    fn pass_for_another_remoteip_is_rejected() {
+        let pass = cache().issue(&captcha(), &response()).unwrap();
+
+        let result = cache().verify(&pass, Some("192.0.2.11".parse().unwrap()));
+        assert!(codes(result).contains(&Code::InvalidPass));
+        assert_err!(cache().verify(&pass, None));
+    }
+
+    #[test]
+    fn pass_signed_with_another_key_is_rejected() {
+        let pass = VerificationCache::new(b"another key", Duration::from_secs(600))
+            .issue(&captcha(), &response())
+            .unwrap();
+
+        let result = cache().verify(&pass, Some("192.0.2.10".parse().unwrap()));
+        assert!(codes(result).contains(&Code::InvalidPass));
+    }
+
+    #[test]
+    fn tampered_pass_is_rejected() {
+        let pass = cache().issue(&captcha(), &response()).unwrap();
+        let (_, signature) = pass.split_once('.').unwrap();
+        let payload = r#"{"h":"example.com","t":99999999999}"#;
+        let tampered = format!("{}.{}", hex::encode(payload), signature);
+
+        let result = cache().verify(&tampered, None);
+        assert!(codes(result).contains(&Code::InvalidPass));
+    }
+
+    #[test]
+    fn malformed_pass_is_rejected() {
+        for pass in ["", "nodot", "zz.zz", "00.00"] {
+            assert!(codes(cache().verify(pass, None)).contains(&Code::InvalidPass));
+        }
+    }
+
+    #[test]
+    fn expired_pass_is_rejected() {
+        let pass = cache().issue(&captcha(), &response()).unwrap();
+        let later = SystemTime::now() + Duration::from_secs(601);
+
+        let result = cache().verify_at(&pass, Some("192.0.2.10".parse().unwrap()), later);
+        assert!(codes(result).contains(&Code::ExpiredPass));
+    }
+}
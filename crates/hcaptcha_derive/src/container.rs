@@ -0,0 +1,301 @@
+// SPDX-FileCopyrightText: 2022 jerusdp
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Container attribute `#[hcaptcha(...)]`
+//!
+//! Declares the verification settings used by the generated `verify`
+//! method. The values are checked when the derive is expanded so that a
+//! bad url or score is a compile error rather than a runtime failure.
+
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::meta::ParseNestedMeta;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{bracketed, Attribute, Ident, Lit, LitStr, Token, Type};
+
+/// Settings from the `#[hcaptcha(...)]` attribute on the type.
+#[derive(Debug, Default)]
+pub(crate) struct Container {
+    /// Environment variable holding the secret.
+    secret_env: Option<LitStr>,
+    /// Url for the siteverify endpoint.
+    url: Option<LitStr>,
+    /// Highest score accepted.
+    max_score: Option<f32>,
+    /// Hostnames the captcha may be solved on.
+    hostnames: Option<Vec<LitStr>>,
+}
+
+impl Container {
+    /// Parse the `#[hcaptcha(...)]` attribute from the attributes on the
+    /// type.
+    ///
+    /// Returns `None` if the type has no `#[hcaptcha(...)]` attribute.
+    pub(crate) fn from_attrs(attrs: &[Attribute], name: &Ident) -> syn::Result<Option<Self>> {
+        let attrs: Vec<&Attribute> = attrs
+            .iter()
+            .filter(|a| a.path().is_ident("hcaptcha"))
+            .collect();
+        if attrs.is_empty() {
+            return Ok(None);
+        }
+
+        let mut container = Container::default();
+        for attr in attrs {
+            attr.parse_nested_meta(|meta| container.parse_meta(meta))?;
+        }
+        if container.secret_env.is_none() {
+            return Err(syn::Error::new_spanned(
+                name,
+                "Secret not identified\n\n\
+                 help: The environment variable holding the secret must be given with\n\
+                 #[hcaptcha(secret_env = \"HCAPTCHA_SECRET\")]",
+            ));
+        }
+        Ok(Some(container))
+    }
+
+    fn parse_meta(&mut self, meta: ParseNestedMeta) -> syn::Result<()> {
+        if meta.path.is_ident("secret_env") {
+            let value: LitStr = meta.value()?.parse()?;
+            let name = value.value();
+            if name.is_empty() || name.contains(['=', '\0']) {
+                return Err(syn::Error::new_spanned(
+                    &value,
+                    "secret_env must be the name of an environment variable",
+                ));
+            }
+            set(&meta, &mut self.secret_env, value)
+        } else if meta.path.is_ident("url") {
+            let value: LitStr = meta.value()?.parse()?;
+            match url::Url::parse(&value.value()) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+                Ok(_) => {
+                    return Err(syn::Error::new_spanned(
+                        &value,
+                        "url must use the http or https scheme",
+                    ))
+                }
+                Err(e) => {
+                    return Err(syn::Error::new_spanned(
+                        &value,
+                        format!("url is not valid: {e}"),
+                    ))
+                }
+            }
+            set(&meta, &mut self.url, value)
+        } else if meta.path.is_ident("max_score") {
+            let value: Lit = meta.value()?.parse()?;
+            let score = match &value {
+                Lit::Float(f) => f.base10_parse::<f32>()?,
+                Lit::Int(i) => i.base10_parse::<f32>()?,
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        &value,
+                        "max_score must be a number",
+                    ))
+                }
+            };
+            if !(0.0..=1.0).contains(&score) {
+                return Err(syn::Error::new_spanned(
+                    &value,
+                    "max_score must be between 0.0 and 1.0",
+                ));
+            }
+            set(&meta, &mut self.max_score, score)
+        } else if meta.path.is_ident("hostnames") {
+            let content;
+            let input = meta.value()?;
+            bracketed!(content in input);
+            let hostnames = Punctuated::<LitStr, Token![,]>::parse_terminated(&content)?;
+            for hostname in &hostnames {
+                let value = hostname.value();
+                if value.is_empty()
+                    || value.contains(|c: char| c.is_whitespace() || "/:@".contains(c))
+                {
+                    return Err(syn::Error::new_spanned(
+                        hostname,
+                        "hostnames must be host names such as \"example.com\"",
+                    ));
+                }
+            }
+            set(&meta, &mut self.hostnames, hostnames.into_iter().collect())
+        } else if meta.path.is_ident("min_score") {
+            Err(meta.error(
+                "unsupported hcaptcha attribute `min_score`\n\n\
+                 help: the hcaptcha score rises with the risk of the request, use max_score \
+                 to set the highest score accepted",
+            ))
+        } else {
+            Err(meta.error(
+                "unknown hcaptcha attribute\n\n\
+                 help: expected one of secret_env, url, max_score, hostnames",
+            ))
+        }
+    }
+
+    /// Generate the `verify` method.
+    ///
+    /// `body` verifies the captcha with `secret` and checks the response
+    /// with `policy`, see [`Container::verify_fields`] and
+    /// [`Container::verify_flattened`].
+    pub(crate) fn verify(&self, body: &TokenStream) -> TokenStream {
+        let secret_env = &self.secret_env;
+        let hostnames = self.hostnames.iter().flatten();
+        let max_score = self
+            .max_score
+            .map(|score| quote! { .set_max_score(#score) });
+
+        quote! {
+            fn verify(&self) -> hcaptcha::HcaptchaFuture {
+                let secret = match std::env::var(#secret_env) {
+                    Ok(s) => s,
+                    Err(e) => {
+                        return Box::pin(async { Err(hcaptcha::Error::from(e)) });
+                    }
+                };
+                let policy = hcaptcha::Policy::new()
+                    #(.add_hostname(#hostnames))*
+                    #max_score;
+                #body
+            }
+        }
+    }
+
+    /// Generate the body of `verify` for marked fields.
+    ///
+    /// `request` builds the `request` from the fields and `secret`.
+    pub(crate) fn verify_fields(&self, request: &TokenStream) -> TokenStream {
+        let set_url = self.url.as_ref().map(|url| {
+            quote! {
+                match client.set_url(#url) {
+                    Ok(c) => client = c,
+                    Err(e) => {
+                        return Box::pin(async { Err(e) });
+                    }
+                };
+            }
+        });
+
+        quote! {
+            #[allow(unused_mut)]
+            let mut client = hcaptcha::Client::new();
+            #set_url
+            #request
+            Box::pin(async move {
+                let response = client.verify_request(request).await?;
+                policy.check(&response)?;
+                Ok(response)
+            })
+        }
+    }
+
+    /// Generate the body of `verify` for a flattened field.
+    ///
+    /// `value` borrows the flattened field of type `ty`, which is verified
+    /// with its `valid_response` and the url given here.
+    pub(crate) fn verify_flattened(&self, ty: &Type, value: &TokenStream) -> TokenStream {
+        let uri = match &self.url {
+            Some(url) => quote! { Some(String::from(#url)) },
+            None => quote! { None },
+        };
+        let args = quote! { #value, &secret, #uri };
+        let valid_response = quote_spanned! {ty.span()=>
+            <#ty as hcaptcha::Hcaptcha>::valid_response(#args)
+        };
+
+        quote! {
+            let response = #valid_response;
+            Box::pin(async move {
+                let response = response.await?;
+                policy.check(&response)?;
+                Ok(response)
+            })
+        }
+    }
+}
+
+/// Set the value of a key, rejecting a key that is given twice.
+fn set<T>(meta: &ParseNestedMeta, slot: &mut Option<T>, value: T) -> syn::Result<()> {
+    if slot.is_some() {
+        return Err(meta.error("duplicate hcaptcha attribute"));
+    }
+    *slot = Some(value);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proc_macro2::Span;
+    use syn::parse::Parser;
+
+    fn parse(attr: &str) -> syn::Result<Option<Container>> {
+        let attrs = Attribute::parse_outer.parse_str(attr).unwrap();
+        Container::from_attrs(&attrs, &Ident::new("Form", Span::call_site()))
+    }
+
+    fn error(attr: &str) -> String {
+        parse(attr)
+            .expect_err("attribute should be rejected")
+            .to_string()
+    }
+
+    #[test]
+    fn no_container_attribute() {
+        assert!(parse("#[derive(Debug)]").unwrap().is_none());
+    }
+
+    #[test]
+    fn all_keys_are_parsed() {
+        let container = parse(
+            r#"#[hcaptcha(secret_env = "HCAPTCHA_SECRET", url = "https://example.com/siteverify",
+                max_score = 0.5, hostnames = ["example.com", "www.example.com"])]"#,
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(container.secret_env.unwrap().value(), "HCAPTCHA_SECRET");
+        assert_eq!(
+            container.url.unwrap().value(),
+            "https://example.com/siteverify"
+        );
+        assert_eq!(container.max_score, Some(0.5));
+        assert_eq!(container.hostnames.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn secret_env_is_required() {
+        assert!(error(r#"#[hcaptcha(max_score = 0.5)]"#).contains("Secret not identified"));
+    }
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        assert!(error(r#"#[hcaptcha(secret_env = "")]"#).contains("environment variable"));
+        assert!(error(r#"#[hcaptcha(secret_env = "S", url = "not a url")]"#)
+            .contains("url is not valid"));
+        assert!(
+            error(r#"#[hcaptcha(secret_env = "S", url = "ftp://example.com")]"#)
+                .contains("http or https")
+        );
+        assert!(error(r#"#[hcaptcha(secret_env = "S", max_score = 1.5)]"#)
+            .contains("between 0.0 and 1.0"));
+        assert!(
+            error(r#"#[hcaptcha(secret_env = "S", max_score = "high")]"#)
+                .contains("must be a number")
+        );
+        assert!(
+            error(r#"#[hcaptcha(secret_env = "S", hostnames = ["https://example.com"])]"#)
+                .contains("host names")
+        );
+    }
+
+    #[test]
+    fn unknown_and_duplicate_keys_are_rejected() {
+        assert!(error(r#"#[hcaptcha(secret_env = "S", min_score = 0.5)]"#).contains("max_score"));
+        assert!(error(r#"#[hcaptcha(secret_env = "S", timeout = 5)]"#).contains("unknown"));
+        assert!(error(r#"#[hcaptcha(secret_env = "S", secret_env = "T")]"#).contains("duplicate"));
+    }
+}
@@ -0,0 +1,238 @@
+// SPDX-FileCopyrightText: 2022 jerusdp
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Field types supported by the derive
+//!
+//! A field may hold any type that implements `AsRef<str>`, such as `String`,
+//! `&str` or `Cow<str>`. The `#[remoteip]` field may also be an `IpAddr`
+//! and the `#[sitekey]` field a `Uuid`. Any of these may be wrapped in an
+//! `Option`.
+//!
+//! A derive sees the type as written and cannot resolve imports, so the ip
+//! address and uuid types are recognised by their path: the bare name, or
+//! the full path such as `std::net::IpAddr` or `uuid::Uuid`. A type alias,
+//! or another type with the same name under a different path, is treated
+//! as a string type.
+
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
+use syn::{GenericArgument, PathArguments, Type, TypePath};
+
+/// Ip address types accepted for the `#[remoteip]` field.
+const IP_TYPES: [&str; 3] = ["IpAddr", "Ipv4Addr", "Ipv6Addr"];
+
+/// Modules the ip address types may be named from.
+const IP_MODULES: [&[&str]; 2] = [&["std", "net"], &["core", "net"]];
+
+/// Modules the uuid type may be named from.
+const UUID_MODULES: [&[&str]; 1] = [&["uuid"]];
+
+/// How the value of a field is passed to the `Captcha` setter for its
+/// attribute.
+pub(crate) enum Setter {
+    /// A string type, passed as a `&str` to the setter that parses it.
+    Str(TokenStream),
+    /// An ip address or uuid, passed by value to the typed `method`.
+    Typed { method: Ident, value: TokenStream },
+}
+
+/// Return the type inside an `Option`, or `None` if the type is not an
+/// `Option`.
+pub(crate) fn option_inner<'a>(ty: &'a Type, attribute: &str) -> syn::Result<Option<&'a Type>> {
+    let Some(inner) = generic_argument(ty, "Option") else {
+        return Ok(None);
+    };
+    if generic_argument(inner, "Option").is_some() {
+        return Err(syn::Error::new_spanned(
+            ty,
+            format!("Nested Option is not supported for the #[{attribute}] field"),
+        ));
+    }
+    Ok(Some(inner))
+}
+
+/// Generate the value passed to the setter for `value`, a reference to a
+/// `ty`.
+///
+/// String types are converted to a `&str` with `AsRef<str>`; the error for
+/// a type that does not implement it points at the field type. An ip
+/// address for the `#[remoteip]` field or a uuid for the `#[sitekey]` field
+/// is cloned for the typed setter.
+pub(crate) fn setter(ty: &Type, attribute: &str, value: TokenStream) -> syn::Result<Setter> {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => {
+            let typed = |method: &str| Setter::Typed {
+                method: Ident::new(method, Span::call_site()),
+                value: quote! { ::core::clone::Clone::clone(#value) },
+            };
+            if is_type(path, &IP_TYPES, &IP_MODULES) {
+                match attribute {
+                    "remoteip" => Ok(typed("set_remoteip_addr")),
+                    _ => Err(unsupported(ty, attribute)),
+                }
+            } else if is_type(path, &["Uuid"], &UUID_MODULES) {
+                match attribute {
+                    "sitekey" => Ok(typed("set_sitekey_uuid")),
+                    _ => Err(unsupported(ty, attribute)),
+                }
+            } else {
+                Ok(Setter::Str(as_ref(ty, value)))
+            }
+        }
+        Type::Reference(_) | Type::Paren(_) | Type::Group(_) => Ok(Setter::Str(as_ref(ty, value))),
+        _ => Err(unsupported(ty, attribute)),
+    }
+}
+
+/// Generate an expression that converts `value`, a reference to a `ty`, to
+/// a `&str`.
+///
+/// Only string types are accepted, for the attributes without a typed
+/// setter.
+pub(crate) fn as_str(ty: &Type, attribute: &str, value: TokenStream) -> syn::Result<TokenStream> {
+    match setter(ty, attribute, value)? {
+        Setter::Str(value) => Ok(value),
+        Setter::Typed { .. } => Err(unsupported(ty, attribute)),
+    }
+}
+
+/// Check if `path` names one of `names`, either bare or from one of
+/// `modules`.
+fn is_type(path: &TypePath, names: &[&str], modules: &[&[&str]]) -> bool {
+    let segments: Vec<_> = path.path.segments.iter().collect();
+    let Some((last, prefix)) = segments.split_last() else {
+        return false;
+    };
+    if !last.arguments.is_none() || !names.iter().any(|name| last.ident == name) {
+        return false;
+    }
+    if prefix.is_empty() {
+        return path.path.leading_colon.is_none();
+    }
+    modules.iter().any(|module| {
+        module.len() == prefix.len()
+            && module
+                .iter()
+                .zip(prefix)
+                .all(|(name, segment)| segment.arguments.is_none() && segment.ident == name)
+    })
+}
+
+fn as_ref(ty: &Type, value: TokenStream) -> TokenStream {
+    quote_spanned! {ty.span()=>
+        <#ty as ::core::convert::AsRef<str>>::as_ref(#value)
+    }
+}
+
+/// Return the single type argument if `ty` is the generic type `name`.
+fn generic_argument<'a>(ty: &'a Type, name: &str) -> Option<&'a Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if path.qself.is_some() || segment.ident != name {
+        return None;
+    }
+    let PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return None;
+    };
+    match arguments.args.first() {
+        Some(GenericArgument::Type(inner)) if arguments.args.len() == 1 => Some(inner),
+        _ => None,
+    }
+}
+
+fn unsupported(ty: &Type, attribute: &str) -> syn::Error {
+    let expected = match attribute {
+        "remoteip" => "a string type such as String, &str or Cow<str>, or an IpAddr",
+        "sitekey" => "a string type such as String, &str or Cow<str>, or a Uuid",
+        _ => "a string type such as String, &str or Cow<str>",
+    };
+    syn::Error::new_spanned(
+        ty,
+        format!(
+            "Unsupported type for the #[{attribute}] field\n\n\
+             help: expected {expected}, or an Option of one of these"
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ty(s: &str) -> Type {
+        syn::parse_str(s).unwrap()
+    }
+
+    #[test]
+    fn option_inner_type_is_found() {
+        let option = ty("Option<String>");
+        let inner = option_inner(&option, "remoteip").unwrap().unwrap();
+        assert_eq!(quote!(#inner).to_string(), "String");
+
+        assert!(option_inner(&ty("String"), "remoteip").unwrap().is_none());
+        assert!(option_inner(&ty("Option<Option<String>>"), "remoteip").is_err());
+    }
+
+    fn typed_method(ty: &Type, attribute: &str) -> Option<String> {
+        match setter(ty, attribute, quote!(value)).unwrap() {
+            Setter::Typed { method, value } => {
+                assert!(value.to_string().contains("Clone"));
+                Some(method.to_string())
+            }
+            Setter::Str(_) => None,
+        }
+    }
+
+    #[test]
+    fn string_types_use_as_ref() {
+        for s in ["String", "&'a str", "std::borrow::Cow<'a, str>", "T"] {
+            let result = as_str(&ty(s), "captcha", quote!(value)).unwrap();
+            assert!(result.to_string().contains("AsRef"), "{}", s);
+        }
+    }
+
+    #[test]
+    fn ip_and_uuid_types_use_typed_setters() {
+        for s in [
+            "IpAddr",
+            "Ipv4Addr",
+            "std::net::IpAddr",
+            "core::net::Ipv6Addr",
+        ] {
+            assert_eq!(
+                typed_method(&ty(s), "remoteip").as_deref(),
+                Some("set_remoteip_addr"),
+                "{}",
+                s
+            );
+        }
+        for s in ["Uuid", "uuid::Uuid"] {
+            assert_eq!(
+                typed_method(&ty(s), "sitekey").as_deref(),
+                Some("set_sitekey_uuid"),
+                "{}",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn types_under_other_paths_use_as_ref() {
+        for s in ["my::IpAddr", "::IpAddr", "std::IpAddr"] {
+            assert_eq!(typed_method(&ty(s), "remoteip"), None, "{}", s);
+        }
+        assert_eq!(typed_method(&ty("my::Uuid"), "sitekey"), None);
+    }
+
+    #[test]
+    fn unsupported_types_are_rejected() {
+        assert!(as_str(&ty("IpAddr"), "captcha", quote!(value)).is_err());
+        assert!(as_str(&ty("Uuid"), "remoteip", quote!(value)).is_err());
+        assert!(as_str(&ty("(String, String)"), "sitekey", quote!(value)).is_err());
+        assert!(as_str(&ty("[u8; 4]"), "remoteip", quote!(value)).is_err());
+    }
+}
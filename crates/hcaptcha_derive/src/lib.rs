@@ -49,14 +49,7 @@
 //!         &self,
 //!         secret: &str,
 //!         uri: Option<String>,
-//!     ) -> std::pin::Pin<
-//!         Box<
-//!             dyn std::future::Future<
-//!                     Output = Result<hcaptcha::Response,
-//!                                     hcaptcha::Error>,
-//!                                     >,
-//!         >,
-//!     > {
+//!     ) -> hcaptcha::HcaptchaFuture {
 //!         let mut client = hcaptcha::Client::new();
 //!         if let Some(u) = uri {
 //!             match client.set_url(&u) {
@@ -95,19 +88,72 @@
 //!         };
 //!         Box::pin(client.verify(request))
 //!     }
+//!
+//!     fn valid_response_with<'a>(
+//!         &self,
+//!         client: &'a hcaptcha::Client,
+//!         secret: &str,
+//!     ) -> hcaptcha::HcaptchaFutureWith<'a> {
+//!         // The same captcha and request as above
+//! #       let captcha = hcaptcha::Captcha::new(&self.hcaptcha).unwrap();
+//! #       let request = hcaptcha::Request::new(&secret, captcha).unwrap();
+//!         Box::pin(client.verify_request(request))
+//!     }
 //! }
 //!```
+//!
+//! The returned [`HcaptchaFuture`](../hcaptcha/type.HcaptchaFuture.html) is
+//! `Send`, except on `wasm32` targets, so `valid_response` can be awaited in
+//! a spawned task or a handler on a multi-threaded executor.
 
 extern crate proc_macro;
 
+mod container;
+mod field_type;
+
 use std::collections::HashMap;
 
 use proc_macro::TokenStream;
 use proc_macro2::Ident;
-use quote::quote;
-use syn::{Data, DataStruct, DeriveInput};
+use quote::{format_ident, quote, quote_spanned, ToTokens};
+use syn::spanned::Spanned;
+use syn::{Attribute, Data, DeriveInput, Field, Fields, Index, Member, Type};
+
+use container::Container;
 
-/// Derive the Hcaptcha trait for a struct.
+/// Derive the Hcaptcha trait for a struct or enum.
+///
+/// Generates `valid_response`, which verifies with a new client, and
+/// `valid_response_with`, which verifies with a shared client.
+///
+/// The `#[hcaptcha(...)]` attribute on the struct declares the settings for
+/// `verify`:
+///
+/// - `secret_env = "NAME"` (required): environment variable holding the
+///   secret.
+/// - `url = "https://..."`: url for the siteverify endpoint.
+/// - `max_score = 0.5`: highest score accepted, between 0.0 and 1.0.
+/// - `hostnames = ["example.com", ...]`: hostnames the captcha may be
+///   solved on.
+///
+/// The values are checked at compile time.
+///
+/// The `#[captcha]`, `#[remoteip]` and `#[sitekey]` fields may be any type
+/// that implements `AsRef<str>`, such as `String`, `&str` or `Cow<str>`.
+/// The `#[remoteip]` field may also be an `IpAddr`, `Ipv4Addr` or
+/// `Ipv6Addr` and the `#[sitekey]` field a `Uuid`, named by the bare type
+/// or by the full path such as `std::net::IpAddr`. A field wrapped in an `Option` is skipped when it is
+/// `None`. The fields may also be marked `#[hcaptcha(captcha)]`,
+/// `#[hcaptcha(remoteip)]` and `#[hcaptcha(sitekey)]`; attributes for other
+/// macros, such as `#[serde(...)]`, are ignored.
+///
+/// The fields of a tuple struct are marked by position. Each variant of an
+/// enum marks its own fields and verifies the variant it holds.
+///
+/// A field whose type derives Hcaptcha may be marked `#[hcaptcha(flatten)]`
+/// in place of the other attributes; the methods then delegate to that
+/// field. The `verify` method uses the settings of the outer type if it has
+/// the `#[hcaptcha(...)]` attribute, and calls `verify` on the field if not.
 ///
 /// # Example
 ///
@@ -115,6 +161,7 @@ use syn::{Data, DataStruct, DeriveInput};
 /// use hcaptcha::Hcaptcha;
 ///
 /// #[derive(Hcaptcha)]
+/// #[hcaptcha(secret_env = "HCAPTCHA_SECRET", hostnames = ["example.com"])]
 /// pub struct ContactForm {
 ///     name: String,
 ///     email: String,
@@ -123,8 +170,18 @@ use syn::{Data, DataStruct, DeriveInput};
 ///     #[captcha]
 ///     token: String,
 /// }
+///
+/// #[derive(Hcaptcha)]
+/// pub enum Submission {
+///     Signup {
+///         email: String,
+///         #[captcha]
+///         token: String,
+///     },
+///     Login(String, #[captcha] String),
+/// }
 /// ```
-#[proc_macro_derive(Hcaptcha, attributes(captcha, remoteip, sitekey))]
+#[proc_macro_derive(Hcaptcha, attributes(captcha, remoteip, sitekey, hcaptcha))]
 pub fn hcaptcha_derive(input: TokenStream) -> TokenStream {
     // Construct a representation of Rust code as a syntax tree
     // that we can manipulate
@@ -140,46 +197,228 @@ fn impl_hcaptcha(ast: &DeriveInput) -> Result<TokenStream, syn::Error> {
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
     let data = &ast.data;
 
-    let data_struct = get_struct_data(data, name)?;
+    let variants = get_variants(data, name)?;
+
+    // Report the errors in the container attribute and in every variant
+    // together.
+    let mut errors: Option<syn::Error> = None;
+    let mut push_error = |error: syn::Error| match &mut errors {
+        Some(errors) => errors.combine(error),
+        None => errors = Some(error),
+    };
+
+    let container = Container::from_attrs(&ast.attrs, name).unwrap_or_else(|e| {
+        push_error(e);
+        None
+    });
+
+    // The source of the captcha for the struct, or for each variant with
+    // the pattern that binds its marked fields.
+    let mut sources = Vec::new();
+    for variant in &variants {
+        for error in variant_attribute_errors(variant.attrs) {
+            push_error(error);
+        }
+        let result =
+            get_attributes(variant.fields).and_then(|mut attributes| match variant.ident {
+                Some(ident) => {
+                    // Bind the marked fields of the variant so that the request
+                    // is built from the bindings.
+                    for (name, marked) in attributes.iter_mut() {
+                        marked.value = format_ident!("__hcaptcha_{}", name).into_token_stream();
+                    }
+                    let members = attributes.values().map(|marked| &marked.member);
+                    let bindings = attributes.values().map(|marked| &marked.value);
+                    let pattern = quote! { Self::#ident { #(#members: #bindings,)* .. } };
+                    Ok((Some(pattern), get_source(&attributes, ident)?))
+                }
+                None => Ok((None, get_source(&attributes, name)?)),
+            });
+        match result {
+            Ok(source) => sources.push(source),
+            Err(e) => push_error(e),
+        }
+    }
+    if let Some(errors) = errors {
+        return Err(errors);
+    }
+
+    // Generate a method body from each source, matching on the variant of
+    // an enum.
+    let body = |generate: &dyn Fn(&Source) -> proc_macro2::TokenStream| {
+        let arms = sources.iter().map(|(pattern, source)| {
+            let body = generate(source);
+            match pattern {
+                Some(pattern) => quote! { #pattern => { #body } },
+                None => body,
+            }
+        });
+        match data {
+            Data::Enum(_) => quote! { match self { #(#arms)* } },
+            _ => quote! { #(#arms)* },
+        }
+    };
 
-    let attributes = get_attributes(data_struct);
+    let valid_response = body(&|source| match source {
+        Source::Fields(request) => quote! {
+            let mut client = hcaptcha::Client::new();
+            if let Some(u) = uri {
+                    match client.set_url(&u)
+                     {
+                        Ok(c) => client = c,
+                        Err(e) => {
+                            return Box::pin(async { Err(e) });
+                        }
+                    };
+            };
 
-    let captcha = get_required_attribute(&attributes, "captcha", name)?;
+            #request
+            Box::pin(client.verify(request))
+        },
+        Source::Flatten { ty, value } => {
+            let args = quote! { #value, secret, uri };
+            quote_spanned! {ty.span()=> <#ty as hcaptcha::Hcaptcha>::valid_response(#args) }
+        }
+    });
+
+    let valid_response_with = body(&|source| match source {
+        Source::Fields(request) => quote! {
+            #request
+            Box::pin(client.verify_request(request))
+        },
+        Source::Flatten { ty, value } => {
+            let args = quote! { #value, client, secret };
+            quote_spanned! {ty.span()=> <#ty as hcaptcha::Hcaptcha>::valid_response_with(#args) }
+        }
+    });
 
-    let remoteip = get_optional_attribute(&attributes, "remoteip", "set_remoteip");
-    let sitekey = get_optional_attribute(&attributes, "sitekey", "set_sitekey");
+    let flattened = sources
+        .iter()
+        .any(|(_, source)| matches!(source, Source::Flatten { .. }));
+    let verify = match container {
+        Some(container) => Some(container.verify(&body(&|source| match source {
+            Source::Fields(request) => container.verify_fields(request),
+            Source::Flatten { ty, value } => container.verify_flattened(ty, value),
+        }))),
+        // Without settings of its own a type delegates `verify` to its
+        // flattened fields.
+        None if flattened => {
+            let body = body(&|source| match source {
+                Source::Fields(_) => quote! {
+                    let mut codes = ::std::collections::HashSet::new();
+                    codes.insert(hcaptcha::Code::MissingSecret);
+                    Box::pin(async { Err(hcaptcha::Error::Codes(codes)) })
+                },
+                Source::Flatten { ty, value } => {
+                    quote_spanned! {ty.span()=> <#ty as hcaptcha::Hcaptcha>::verify(#value) }
+                }
+            });
+            Some(quote! {
+                fn verify(&self) -> hcaptcha::HcaptchaFuture {
+                    #body
+                }
+            })
+        }
+        None => None,
+    };
 
     let gen = quote! {
         impl #impl_generics Hcaptcha for #name #ty_generics #where_clause {
-            fn valid_response(&self, secret: &str, uri: Option<String>) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<hcaptcha::Response, hcaptcha::Error>> >>  {
-                let mut client = hcaptcha::Client::new();
-                if let Some(u) = uri {
-                        match client.set_url(&u)
-                         {
-                            Ok(c) => client = c,
-                            Err(e) => {
-                                return Box::pin(async { Err(e) });
-                            }
-                        };
-                };
+            fn valid_response(&self, secret: &str, uri: Option<String>) -> hcaptcha::HcaptchaFuture {
+                #valid_response
+            }
 
-                #captcha
-                #remoteip
-                #sitekey;
-                let request;
-                match hcaptcha::Request::new(&secret, captcha) {
-                    Ok(r) => request = r,
-                    Err(e) => {
-                        return Box::pin(async { Err(e) } );
-                    }
-                };
-                Box::pin(client.verify(request))
+            fn valid_response_with<'hcaptcha_client>(&self, client: &'hcaptcha_client hcaptcha::Client, secret: &str) -> hcaptcha::HcaptchaFutureWith<'hcaptcha_client> {
+                #valid_response_with
             }
+
+            #verify
         }
     };
     Ok(gen.into())
 }
 
+/// How a struct or variant provides the captcha.
+enum Source<'a> {
+    /// Fields marked `#[captcha]`, `#[remoteip]` and `#[sitekey]`, with the
+    /// statements that build `request` from them.
+    Fields(proc_macro2::TokenStream),
+    /// A field marked `#[hcaptcha(flatten)]` of type `ty`, borrowed by
+    /// `value`, that provides the captcha itself.
+    Flatten {
+        ty: &'a Type,
+        value: proc_macro2::TokenStream,
+    },
+}
+
+/// Find the source of the captcha in the marked fields.
+///
+/// # Errors
+///
+/// An error is returned if the captcha field is missing or has an
+/// unsupported type, or if a flattened field is combined with other marked
+/// fields.
+fn get_source<'a>(
+    attributes: &HashMap<String, MarkedField<'a>>,
+    id: &Ident,
+) -> Result<Source<'a>, syn::Error> {
+    let Some(flatten) = attributes.get("flatten") else {
+        return get_request(attributes, id).map(Source::Fields);
+    };
+
+    let mut others: Vec<(&String, &MarkedField)> = attributes
+        .iter()
+        .filter(|(name, _)| *name != "flatten")
+        .collect();
+    others.sort_by_key(|(name, _)| *name);
+    let mut errors = others.into_iter().map(|(name, marked)| {
+        syn::Error::new_spanned(
+            marked.field,
+            format!(
+                "#[{name}] cannot be combined with #[hcaptcha(flatten)]\n\n\
+                 help: the flattened field provides the captcha, remoteip and sitekey"
+            ),
+        )
+    });
+    if let Some(mut error) = errors.next() {
+        errors.for_each(|e| error.combine(e));
+        return Err(error);
+    }
+
+    let field: &'a Field = flatten.field;
+    Ok(Source::Flatten {
+        ty: &field.ty,
+        value: flatten.value.clone(),
+    })
+}
+
+/// Generate the statements that build `request` from the marked fields
+/// and `secret`.
+///
+/// `id` is the struct or variant reported if the captcha field is missing.
+fn get_request(
+    attributes: &HashMap<String, MarkedField>,
+    id: &Ident,
+) -> Result<proc_macro2::TokenStream, syn::Error> {
+    let captcha = get_required_attribute(attributes, "captcha", id)?;
+
+    let remoteip = get_optional_attribute(attributes, "remoteip", "set_remoteip")?;
+    let sitekey = get_optional_attribute(attributes, "sitekey", "set_sitekey")?;
+
+    Ok(quote! {
+        #captcha
+        #remoteip
+        #sitekey;
+        let request;
+        match hcaptcha::Request::new(&secret, captcha) {
+            Ok(r) => request = r,
+            Err(e) => {
+                return Box::pin(async { Err(e) } );
+            }
+        };
+    })
+}
+
 /// Generate tokens for optional attribute
 ///
 /// # inputs
@@ -191,26 +430,47 @@ fn impl_hcaptcha(ast: &DeriveInput) -> Result<TokenStream, syn::Error> {
 ///
 /// Token stream for the method if the named attributed is found
 /// Empty token stream of the attribute is not found
+/// If the field is an `Option` the method is only called for `Some`
+/// An ip address or uuid field is passed to the typed setter in place of
+/// the method
+/// Generate compiler error if the field type is not supported
 ///
 fn get_optional_attribute(
-    attributes: &HashMap<String, &proc_macro2::Ident>,
+    attributes: &HashMap<String, MarkedField>,
     name: &str,
     method: &str,
-) -> proc_macro2::TokenStream {
-    let method = quote::format_ident!("{}", method);
-    match attributes.get(name) {
-        Some(i) => {
-            let i = <&proc_macro2::Ident>::clone(i);
-            quote! {
-                match captcha.#method(&self.#i) {
+) -> Result<proc_macro2::TokenStream, syn::Error> {
+    let method = format_ident!("{}", method);
+    let Some(marked) = attributes.get(name) else {
+        return Ok(quote! {});
+    };
+    let field = marked.field;
+    let field_value = &marked.value;
+    let set = |ty, value| -> Result<proc_macro2::TokenStream, syn::Error> {
+        match field_type::setter(ty, name, value)? {
+            field_type::Setter::Str(value) => Ok(quote! {
+                match captcha.#method(#value) {
                     Ok(c) => captcha = c,
                     Err(e) => {
                         return Box::pin(async { Err(e) });
                     }
                 };
-            }
+            }),
+            field_type::Setter::Typed { method, value } => Ok(quote! {
+                captcha = captcha.#method(#value);
+            }),
+        }
+    };
+    match field_type::option_inner(&field.ty, name)? {
+        Some(inner) => {
+            let set = set(inner, quote! { value })?;
+            Ok(quote! {
+                if let Some(value) = #field_value {
+                    #set
+                }
+            })
         }
-        None => quote! {},
+        None => set(&field.ty, field_value.clone()),
     }
 }
 
@@ -223,20 +483,33 @@ fn get_optional_attribute(
 /// # Output
 ///
 /// Token stream for the method if the named attributed is found
-/// Generate compiler error if not found
+/// Generate compiler error if not found or the field type is not supported
 ///
 fn get_required_attribute(
-    attributes: &HashMap<String, &proc_macro2::Ident>,
+    attributes: &HashMap<String, MarkedField>,
     name: &str,
     id: &proc_macro2::Ident,
 ) -> Result<proc_macro2::TokenStream, syn::Error> {
     match attributes.get(name) {
-        Some(i) => {
-            let i = <&proc_macro2::Ident>::clone(i);
+        Some(marked) => {
+            let field = marked.field;
+            let field_value = &marked.value;
+            let value = match field_type::option_inner(&field.ty, name)? {
+                Some(inner) => {
+                    let value = field_type::as_str(inner, name, quote! { value })?;
+                    quote! {
+                        match #field_value {
+                            Some(value) => #value,
+                            None => "",
+                        }
+                    }
+                }
+                None => field_type::as_str(&field.ty, name, field_value.clone())?,
+            };
             Ok(quote! {
                 #[allow(unused_mut)]
                 let mut captcha;
-                match hcaptcha::Captcha::new(&self.#i) {
+                match hcaptcha::Captcha::new(#value) {
                     Ok(c) => captcha = c,
                     Err(e) => {
                         return Box::pin(async{Err(e)});
@@ -263,50 +536,193 @@ fn get_required_attribute(
     }
 }
 
-/// Extract the tokens for the Struct from the data
-/// If the data is not stored in the Struct variant abort compilation
+/// The fields of a struct or of one variant of an enum.
+struct Variant<'a> {
+    /// The variant name, `None` for a struct.
+    ident: Option<&'a Ident>,
+    attrs: &'a [Attribute],
+    fields: &'a Fields,
+}
+
+/// Extract the fields of the struct, or of each variant of the enum, from
+/// the data.
+/// If the data is a union or an enum without variants abort compilation
 /// with an error.
-fn get_struct_data<'a>(data: &'a Data, name: &Ident) -> Result<&'a DataStruct, syn::Error> {
-    match data {
-        Data::Struct(s) => Ok(s),
-        _ => {
-            let example = r#"
+fn get_variants<'a>(data: &'a Data, name: &Ident) -> Result<Vec<Variant<'a>>, syn::Error> {
+    let example = r#"
         #[derive(Hcaptcha)]
         struct MyStruct {
             #[captcha]
             hcaptcha: String,
         }"#;
-            Err(syn::Error::new_spanned(
-                name,
-                format!(
-                    "Must derive on a struct\n\n\
-                     help: This macro can only be implemented on a struct.\n\
-                     {example}"
-                ),
-            ))
+    match data {
+        Data::Struct(s) => Ok(vec![Variant {
+            ident: None,
+            attrs: &[],
+            fields: &s.fields,
+        }]),
+        Data::Enum(e) if !e.variants.is_empty() => Ok(e
+            .variants
+            .iter()
+            .map(|v| Variant {
+                ident: Some(&v.ident),
+                attrs: &v.attrs,
+                fields: &v.fields,
+            })
+            .collect()),
+        Data::Enum(_) => Err(syn::Error::new_spanned(
+            name,
+            "Must derive on an enum with at least one variant",
+        )),
+        Data::Union(_) => Err(syn::Error::new_spanned(
+            name,
+            format!(
+                "Must derive on a struct or enum\n\n\
+                 help: This macro can only be implemented on a struct or enum.\n\
+                 {example}"
+            ),
+        )),
+    }
+}
+
+/// Report hcaptcha attributes placed on an enum variant rather than on one
+/// of its fields.
+fn variant_attribute_errors(attrs: &[Attribute]) -> Vec<syn::Error> {
+    attrs
+        .iter()
+        .filter(|attr| {
+            FIELD_ATTRIBUTES
+                .iter()
+                .chain(&["hcaptcha"])
+                .any(|name| attr.path().is_ident(name))
+        })
+        .map(|attr| {
+            syn::Error::new_spanned(
+                attr,
+                "hcaptcha attributes must be placed on a field of the variant\n\n\
+                 help: mark the field holding the response, for example\n\
+                 Signup { #[captcha] token: String }",
+            )
+        })
+        .collect()
+}
+
+/// A field marked with an hcaptcha attribute.
+struct MarkedField<'a> {
+    field: &'a Field,
+    /// The name or position of the field.
+    member: Member,
+    /// Expression borrowing the value of the field.
+    value: proc_macro2::TokenStream,
+}
+
+impl<'a> MarkedField<'a> {
+    fn new(field: &'a Field, index: usize) -> Self {
+        let member = match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(Index::from(index)),
+        };
+        let value = quote! { &self.#member };
+        MarkedField {
+            field,
+            member,
+            value,
         }
     }
 }
 
-/// Iterate through the fields in the struct to find the attributes
-/// that identify the fields relevant to hcaptcha processing
-fn get_attributes(data_struct: &DataStruct) -> HashMap<String, &Ident> {
-    let mut attributes = HashMap::new();
+/// Attributes that identify the fields relevant to hcaptcha processing.
+const FIELD_ATTRIBUTES: [&str; 3] = ["captcha", "remoteip", "sitekey"];
 
-    let _: Vec<String> = data_struct
-        .fields
-        .iter()
-        .filter(|f| !f.attrs.is_empty())
-        .map(|f| {
-            let a = f.attrs[0].path().get_ident().unwrap().to_string();
-            if let Some(i) = &f.ident {
-                attributes.insert(a.clone(), i);
+/// Iterate through the fields of the struct or variant to find the
+/// attributes that identify the fields relevant to hcaptcha processing
+///
+/// Every attribute on every field is checked. Attributes for other macros,
+/// such as `#[serde(...)]` or `#[allow(...)]`, are ignored. A field may
+/// also be identified with `#[hcaptcha(captcha)]`, `#[hcaptcha(remoteip)]`
+/// or `#[hcaptcha(sitekey)]`.
+///
+/// # Errors
+///
+/// An error is returned for each attribute that is repeated, given
+/// arguments or not understood, and for a field with more than one
+/// attribute.
+fn get_attributes(fields: &Fields) -> Result<HashMap<String, MarkedField<'_>>, syn::Error> {
+    let mut attributes: HashMap<String, MarkedField> = HashMap::new();
+    let mut errors: Option<syn::Error> = None;
+    let mut push_error = |error: syn::Error| match &mut errors {
+        Some(errors) => errors.combine(error),
+        None => errors = Some(error),
+    };
+
+    for (index, field) in fields.iter().enumerate() {
+        let mut field_attribute: Option<String> = None;
+        for (name, span) in field_attributes(field, &mut push_error) {
+            if let Some(first) = &field_attribute {
+                push_error(syn::Error::new(
+                    span,
+                    format!("#[{name}] conflicts with #[{first}] on the same field"),
+                ));
+            } else if attributes.contains_key(&name) {
+                push_error(syn::Error::new(
+                    span,
+                    format!(
+                        "Duplicate #[{name}] attribute
+
+help: only one field may be marked #[{name}]"
+                    ),
+                ));
+            } else {
+                attributes.insert(name.clone(), MarkedField::new(field, index));
+                field_attribute = Some(name);
             }
-            a
-        })
-        .collect();
+        }
+    }
+
+    match errors {
+        Some(errors) => Err(errors),
+        None => Ok(attributes),
+    }
+}
 
-    attributes
+/// Collect the hcaptcha attributes on a field with the span to report
+/// errors at.
+fn field_attributes(
+    field: &Field,
+    push_error: &mut impl FnMut(syn::Error),
+) -> Vec<(String, proc_macro2::Span)> {
+    let mut found = Vec::new();
+    for attr in &field.attrs {
+        let Some(ident) = attr.path().get_ident() else {
+            continue;
+        };
+        let name = ident.to_string();
+        if FIELD_ATTRIBUTES.contains(&name.as_str()) {
+            match attr.meta.require_path_only() {
+                Ok(_) => found.push((name, ident.span())),
+                Err(_) => push_error(syn::Error::new_spanned(
+                    &attr.meta,
+                    format!("#[{name}] does not take arguments"),
+                )),
+            }
+        } else if name == "hcaptcha" {
+            let result =
+                attr.parse_nested_meta(|meta| match meta.path.get_ident().map(|i| i.to_string()) {
+                    Some(key) if FIELD_ATTRIBUTES.contains(&key.as_str()) || key == "flatten" => {
+                        found.push((key, meta.path.span()));
+                        Ok(())
+                    }
+                    _ => Err(meta.error(
+                        "unknown hcaptcha field attribute\n\n\
+                         help: expected one of captcha, remoteip, sitekey, flatten",
+                    )),
+                });
+            if let Err(e) = result {
+                push_error(e);
+            }
+        }
+    }
+    found
 }
 
 #[cfg(test)]
@@ -315,20 +731,26 @@ mod tests {
     use quote::format_ident;
     use std::iter::FromIterator;
     use syn::parse::Parser;
-    use syn::{Attribute, Field, Fields, FieldsNamed};
+    use syn::{DataStruct, FieldsNamed};
 
     use super::*;
 
+    fn field(name: &str, ty: &str) -> Field {
+        Field::parse_named
+            .parse_str(&format!("{name}: {ty}"))
+            .unwrap()
+    }
+
     #[test]
     fn test_get_optional_attribute_with_valid_attribute() {
         let mut attributes = HashMap::new();
-        let ident = Ident::new("test_field", Span::call_site());
-        attributes.insert("test".to_string(), &ident);
+        let field = field("test_field", "String");
+        attributes.insert("test".to_string(), MarkedField::new(&field, 0));
 
-        let result = get_optional_attribute(&attributes, "test", "test_method");
+        let result = get_optional_attribute(&attributes, "test", "test_method").unwrap();
 
         let expected = quote! {
-            match captcha.test_method(&self.test_field) {
+            match captcha.test_method(<String as ::core::convert::AsRef<str>>::as_ref(&self.test_field)) {
                 Ok(c) => captcha = c,
                 Err(e) => {
                     return Box::pin(async { Err(e) });
@@ -343,7 +765,7 @@ mod tests {
     fn test_get_optional_attribute_with_missing_attribute() {
         let attributes = HashMap::new();
 
-        let result = get_optional_attribute(&attributes, "test", "test_method");
+        let result = get_optional_attribute(&attributes, "test", "test_method").unwrap();
 
         assert_eq!(result.to_string(), quote! {}.to_string());
     }
@@ -351,13 +773,13 @@ mod tests {
     #[test]
     fn test_get_optional_attribute_with_different_method_name() {
         let mut attributes = HashMap::new();
-        let ident = Ident::new("field", Span::call_site());
-        attributes.insert("attr".to_string(), &ident);
+        let field = field("field", "String");
+        attributes.insert("attr".to_string(), MarkedField::new(&field, 0));
 
-        let result = get_optional_attribute(&attributes, "attr", "custom_method");
+        let result = get_optional_attribute(&attributes, "attr", "custom_method").unwrap();
 
         let expected = quote! {
-            match captcha.custom_method(&self.field) {
+            match captcha.custom_method(<String as ::core::convert::AsRef<str>>::as_ref(&self.field)) {
                 Ok(c) => captcha = c,
                 Err(e) => {
                     return Box::pin(async { Err(e) });
@@ -371,10 +793,10 @@ mod tests {
     #[test]
     fn test_get_required_attribute_with_valid_field() {
         let mut attributes = HashMap::new();
-        let field_ident = Ident::new("hcaptcha_field", Span::call_site());
+        let field = field("hcaptcha_field", "String");
         let struct_ident = Ident::new("TestStruct", Span::call_site());
 
-        attributes.insert("captcha".to_string(), &field_ident);
+        attributes.insert("captcha".to_string(), MarkedField::new(&field, 0));
 
         let result = get_required_attribute(&attributes, "captcha", &struct_ident);
         assert!(!result.unwrap().is_empty());
@@ -383,10 +805,10 @@ mod tests {
     #[test]
     fn test_get_required_attribute_wrong_field_name() {
         let mut attributes = HashMap::new();
-        let field_ident = Ident::new("hcaptcha_field", Span::call_site());
+        let field = field("hcaptcha_field", "String");
         let struct_ident = Ident::new("TestStruct", Span::call_site());
 
-        attributes.insert("wrong_name".to_string(), &field_ident);
+        attributes.insert("wrong_name".to_string(), MarkedField::new(&field, 0));
 
         // Missing #[captcha] field now yields a recoverable syn::Error
         // (emitted as a compile_error!) instead of aborting/panicking.
@@ -400,19 +822,19 @@ mod tests {
     #[test]
     fn test_get_required_attribute_multiple_fields() {
         let mut attributes = HashMap::new();
-        let captcha_field = Ident::new("hcaptcha_field", Span::call_site());
-        let other_field = Ident::new("other_field", Span::call_site());
+        let captcha_field = field("hcaptcha_field", "String");
+        let other_field = field("other_field", "String");
         let struct_ident = Ident::new("TestStruct", Span::call_site());
 
-        attributes.insert("captcha".to_string(), &captcha_field);
-        attributes.insert("other".to_string(), &other_field);
+        attributes.insert("captcha".to_string(), MarkedField::new(&captcha_field, 0));
+        attributes.insert("other".to_string(), MarkedField::new(&other_field, 0));
 
         let result = get_required_attribute(&attributes, "captcha", &struct_ident);
         assert!(!result.unwrap().is_empty());
     }
 
     #[test]
-    fn test_get_struct_data_valid_struct() {
+    fn test_get_variants_valid_struct() {
         let name = Ident::new("TestStruct", Span::call_site());
         let fields = Fields::Named(FieldsNamed {
             brace_token: Default::default(),
@@ -425,8 +847,37 @@ mod tests {
         };
         let data = Data::Struct(data_struct.clone());
 
-        let result = get_struct_data(&data, &name);
-        assert_eq!(result.unwrap(), &data_struct);
+        let variants = get_variants(&data, &name).unwrap();
+        assert_eq!(variants.len(), 1);
+        assert!(variants[0].ident.is_none());
+        assert_eq!(variants[0].fields, &data_struct.fields);
+    }
+
+    #[test]
+    fn test_get_variants_enum() {
+        let input: DeriveInput = syn::parse_str(
+            "enum Form { Signup { #[captcha] token: String }, Login(#[captcha] String) }",
+        )
+        .unwrap();
+
+        let variants = get_variants(&input.data, &input.ident).unwrap();
+        let names: Vec<String> = variants
+            .iter()
+            .map(|v| v.ident.unwrap().to_string())
+            .collect();
+        assert_eq!(names, ["Signup", "Login"]);
+    }
+
+    #[test]
+    fn test_get_variants_rejects_empty_enum_and_union() {
+        for source in ["enum Form {}", "union Form { token: u32 }"] {
+            let input: DeriveInput = syn::parse_str(source).unwrap();
+            assert!(
+                get_variants(&input.data, &input.ident).is_err(),
+                "{}",
+                source
+            );
+        }
     }
 
     #[test]
@@ -440,14 +891,14 @@ mod tests {
             semi_token: None,
         };
 
-        let attributes = get_attributes(&data_struct);
+        let attributes = get_attributes(&data_struct.fields).unwrap();
         assert!(attributes.is_empty());
     }
 
     #[test]
     fn test_get_attributes_with_single_field() {
         let ident = format_ident!("field_name");
-        let attr = Attribute::parse_outer.parse_str("#[serde]").unwrap();
+        let attr = Attribute::parse_outer.parse_str("#[captcha]").unwrap();
 
         let field = Field {
             attrs: vec![attr[0].clone()],
@@ -468,15 +919,25 @@ mod tests {
             semi_token: None,
         };
 
-        let attributes = get_attributes(&data_struct);
+        let attributes = get_attributes(&data_struct.fields).unwrap();
         assert_eq!(attributes.len(), 1);
-        assert_eq!(attributes.get("serde").unwrap().to_string(), "field_name");
+        assert_eq!(
+            attributes
+                .get("captcha")
+                .unwrap()
+                .field
+                .ident
+                .as_ref()
+                .unwrap()
+                .to_string(),
+            "field_name"
+        );
     }
 
     #[test]
     fn test_get_attributes_multiple_fields() {
         let field1 = Field {
-            attrs: vec![Attribute::parse_outer.parse_str("#[serde]").unwrap()[0].clone()],
+            attrs: vec![Attribute::parse_outer.parse_str("#[captcha]").unwrap()[0].clone()],
             vis: syn::Visibility::Inherited,
             ident: Some(format_ident!("field1")),
             colon_token: None,
@@ -486,7 +947,7 @@ mod tests {
         };
 
         let field2 = Field {
-            attrs: vec![Attribute::parse_outer.parse_str("#[rename]").unwrap()[0].clone()],
+            attrs: vec![Attribute::parse_outer.parse_str("#[remoteip]").unwrap()[0].clone()],
             vis: syn::Visibility::Inherited,
             ident: Some(format_ident!("field2")),
             colon_token: None,
@@ -504,10 +965,30 @@ mod tests {
             semi_token: None,
         };
 
-        let attributes = get_attributes(&data_struct);
+        let attributes = get_attributes(&data_struct.fields).unwrap();
         assert_eq!(attributes.len(), 2);
-        assert_eq!(attributes.get("serde").unwrap().to_string(), "field1");
-        assert_eq!(attributes.get("rename").unwrap().to_string(), "field2");
+        assert_eq!(
+            attributes
+                .get("captcha")
+                .unwrap()
+                .field
+                .ident
+                .as_ref()
+                .unwrap()
+                .to_string(),
+            "field1"
+        );
+        assert_eq!(
+            attributes
+                .get("remoteip")
+                .unwrap()
+                .field
+                .ident
+                .as_ref()
+                .unwrap()
+                .to_string(),
+            "field2"
+        );
     }
 
     #[test]
@@ -531,7 +1012,200 @@ mod tests {
             semi_token: None,
         };
 
-        let attributes = get_attributes(&data_struct);
+        let attributes = get_attributes(&data_struct.fields).unwrap();
         assert!(attributes.is_empty());
     }
+
+    #[test]
+    fn test_get_optional_attribute_with_option_field() {
+        let mut attributes = HashMap::new();
+        let field = field("ip", "Option<std::net::IpAddr>");
+        attributes.insert("remoteip".to_string(), MarkedField::new(&field, 0));
+
+        let result = get_optional_attribute(&attributes, "remoteip", "set_remoteip").unwrap();
+
+        let expected = quote! {
+            if let Some(value) = &self.ip {
+                captcha = captcha.set_remoteip_addr(::core::clone::Clone::clone(value));
+            }
+        };
+
+        assert_eq!(result.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn test_get_optional_attribute_with_uuid_field() {
+        let mut attributes = HashMap::new();
+        let field = field("key", "uuid::Uuid");
+        attributes.insert("sitekey".to_string(), MarkedField::new(&field, 0));
+
+        let result = get_optional_attribute(&attributes, "sitekey", "set_sitekey").unwrap();
+
+        let expected = quote! {
+            captcha = captcha.set_sitekey_uuid(::core::clone::Clone::clone(&self.key));
+        };
+
+        assert_eq!(result.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn test_get_required_attribute_with_option_field() {
+        let mut attributes = HashMap::new();
+        let field = field("token", "Option<String>");
+        let struct_ident = Ident::new("TestStruct", Span::call_site());
+        attributes.insert("captcha".to_string(), MarkedField::new(&field, 0));
+
+        let result = get_required_attribute(&attributes, "captcha", &struct_ident).unwrap();
+        assert!(result.to_string().contains("None => \"\""));
+    }
+
+    #[test]
+    fn test_get_required_attribute_with_unsupported_type() {
+        let mut attributes = HashMap::new();
+        let field = field("token", "std::net::IpAddr");
+        let struct_ident = Ident::new("TestStruct", Span::call_site());
+        attributes.insert("captcha".to_string(), MarkedField::new(&field, 0));
+
+        let err = get_required_attribute(&attributes, "captcha", &struct_ident)
+            .expect_err("an ip address is not a captcha token");
+        assert!(err.to_string().contains("Unsupported type"));
+    }
+
+    fn struct_data(source: &str) -> DataStruct {
+        match syn::parse_str::<DeriveInput>(source).unwrap().data {
+            Data::Struct(s) => s,
+            _ => panic!("Expected a struct"),
+        }
+    }
+
+    fn attribute_error(source: &str) -> String {
+        let Err(errors) = get_attributes(&struct_data(source).fields) else {
+            panic!("attributes should be rejected");
+        };
+        errors
+            .into_iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_get_attributes_ignores_unrelated_attributes() {
+        let data_struct = struct_data(
+            r#"struct Form {
+                #[serde(rename = "h-captcha-response")]
+                #[allow(dead_code)]
+                #[captcha]
+                token: String,
+                #[serde::rename]
+                #[doc = "The remote ip"]
+                #[hcaptcha(remoteip)]
+                ip: String,
+            }"#,
+        );
+
+        let attributes = get_attributes(&data_struct.fields).unwrap();
+        assert_eq!(attributes.len(), 2);
+        assert_eq!(
+            attributes["captcha"]
+                .field
+                .ident
+                .as_ref()
+                .unwrap()
+                .to_string(),
+            "token"
+        );
+        assert_eq!(
+            attributes["remoteip"]
+                .field
+                .ident
+                .as_ref()
+                .unwrap()
+                .to_string(),
+            "ip"
+        );
+    }
+
+    #[test]
+    fn test_get_attributes_rejects_duplicates() {
+        let error =
+            attribute_error("struct Form { #[captcha] first: String, #[captcha] second: String }");
+        assert!(error.contains("Duplicate #[captcha] attribute"));
+    }
+
+    #[test]
+    fn test_get_attributes_rejects_conflicting_attributes() {
+        let error = attribute_error("struct Form { #[captcha] #[sitekey] token: String }");
+        assert!(error.contains("#[sitekey] conflicts with #[captcha]"));
+    }
+
+    #[test]
+    fn test_get_attributes_rejects_unknown_keys_and_arguments() {
+        let error = attribute_error(
+            "struct Form { #[hcaptcha(token)] token: String, #[remoteip(v4)] ip: String }",
+        );
+        assert!(error.contains("unknown hcaptcha field attribute"));
+        assert!(error.contains("#[remoteip] does not take arguments"));
+    }
+
+    #[test]
+    fn test_get_attributes_tuple_struct() {
+        let data_struct =
+            struct_data("struct Form(String, #[captcha] String, #[remoteip] String);");
+
+        let attributes = get_attributes(&data_struct.fields).unwrap();
+        assert_eq!(attributes["captcha"].value.to_string(), "& self . 1");
+        assert_eq!(attributes["remoteip"].value.to_string(), "& self . 2");
+    }
+
+    #[test]
+    fn test_variant_attribute_errors() {
+        let input: DeriveInput = syn::parse_str(
+            "enum Form { #[captcha] Login(String), #[doc = \"Signup\"] Signup(String) }",
+        )
+        .unwrap();
+        let Data::Enum(data) = input.data else {
+            panic!("Expected an enum")
+        };
+
+        let errors: Vec<usize> = data
+            .variants
+            .iter()
+            .map(|v| variant_attribute_errors(&v.attrs).len())
+            .collect();
+        assert_eq!(errors, [1, 0]);
+    }
+
+    #[test]
+    fn test_get_source_flatten() {
+        let data_struct = struct_data(
+            "struct Form { email: String, #[hcaptcha(flatten)] captcha: CaptchaFields }",
+        );
+        let attributes = get_attributes(&data_struct.fields).unwrap();
+        let id = Ident::new("Form", Span::call_site());
+
+        match get_source(&attributes, &id).unwrap() {
+            Source::Flatten { ty, value } => {
+                assert_eq!(quote!(#ty).to_string(), "CaptchaFields");
+                assert_eq!(value.to_string(), "& self . captcha");
+            }
+            Source::Fields(_) => panic!("Expected the flattened field"),
+        }
+    }
+
+    #[test]
+    fn test_get_source_rejects_flatten_with_marked_fields() {
+        let data_struct = struct_data(
+            "struct Form { #[hcaptcha(flatten)] captcha: CaptchaFields, #[remoteip] ip: String }",
+        );
+        let attributes = get_attributes(&data_struct.fields).unwrap();
+        let id = Ident::new("Form", Span::call_site());
+
+        let Err(error) = get_source(&attributes, &id) else {
+            panic!("flatten should not be combined with #[remoteip]");
+        };
+        assert!(error
+            .to_string()
+            .contains("#[remoteip] cannot be combined with #[hcaptcha(flatten)]"));
+    }
 }
@@ -0,0 +1,33 @@
+// SPDX-FileCopyrightText: 2022 jerusdp
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use hcaptcha::Hcaptcha;
+
+#[derive(Hcaptcha)]
+pub struct CaptchaFields {
+    #[captcha]
+    token: String,
+}
+
+#[derive(Hcaptcha)]
+pub struct ContactForm {
+    #[hcaptcha(flatten)]
+    captcha: CaptchaFields,
+    #[remoteip]
+    ip: String,
+}
+
+pub struct NotHcaptcha {
+    pub token: String,
+}
+
+#[derive(Hcaptcha)]
+pub struct SignupForm {
+    #[hcaptcha(flatten)]
+    captcha: NotHcaptcha,
+}
+
+fn main() {
+    println!("hello");
+}
@@ -0,0 +1,16 @@
+// SPDX-FileCopyrightText: 2022 jerusdp
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use hcaptcha_derive::Hcaptcha;
+
+#[derive(Hcaptcha)]
+#[hcaptcha(secret_env = "HCAPTCHA_SECRET", url = "hcaptcha.com/siteverify")]
+pub struct ContactForm {
+    #[captcha]
+    token: String,
+}
+
+fn main() {
+    println!("hello");
+}
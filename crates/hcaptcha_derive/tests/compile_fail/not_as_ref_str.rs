@@ -0,0 +1,15 @@
+// SPDX-FileCopyrightText: 2022 jerusdp
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use hcaptcha::Hcaptcha;
+
+#[derive(Hcaptcha)]
+pub struct ContactForm {
+    #[captcha]
+    token: u64,
+}
+
+fn main() {
+    println!("hello");
+}
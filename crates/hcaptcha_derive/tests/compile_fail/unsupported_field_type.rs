@@ -0,0 +1,17 @@
+// SPDX-FileCopyrightText: 2022 jerusdp
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use hcaptcha_derive::Hcaptcha;
+
+#[derive(Hcaptcha)]
+pub struct ContactForm {
+    #[captcha]
+    token: String,
+    #[remoteip]
+    ip: (u8, u8, u8, u8),
+}
+
+fn main() {
+    println!("hello");
+}
@@ -34,11 +34,7 @@ impl Hcaptcha for ContactForm {
         &self,
         secret: &str,
         uri: Option<String>,
-    ) -> std::pin::Pin<
-        Box<
-            dyn std::future::Future<Output = Result<hcaptcha::Response, hcaptcha::Error>>,
-        >,
-    > {
+    ) -> hcaptcha::HcaptchaFuture {
         let mut client = hcaptcha::Client::new();
         if let Some(u) = uri {
             match client.set_url(&u) {
@@ -50,7 +46,9 @@ impl Hcaptcha for ContactForm {
         }
         #[allow(unused_mut)]
         let mut captcha;
-        match hcaptcha::Captcha::new(&self.token) {
+        match hcaptcha::Captcha::new(
+            <String as ::core::convert::AsRef<str>>::as_ref(&self.token),
+        ) {
             Ok(c) => captcha = c,
             Err(e) => {
                 return Box::pin(async { Err(e) });
@@ -65,4 +63,28 @@ impl Hcaptcha for ContactForm {
         };
         Box::pin(client.verify(request))
     }
+    fn valid_response_with<'hcaptcha_client>(
+        &self,
+        client: &'hcaptcha_client hcaptcha::Client,
+        secret: &str,
+    ) -> hcaptcha::HcaptchaFutureWith<'hcaptcha_client> {
+        #[allow(unused_mut)]
+        let mut captcha;
+        match hcaptcha::Captcha::new(
+            <String as ::core::convert::AsRef<str>>::as_ref(&self.token),
+        ) {
+            Ok(c) => captcha = c,
+            Err(e) => {
+                return Box::pin(async { Err(e) });
+            }
+        };
+        let request;
+        match hcaptcha::Request::new(&secret, captcha) {
+            Ok(r) => request = r,
+            Err(e) => {
+                return Box::pin(async { Err(e) });
+            }
+        };
+        Box::pin(client.verify_request(request))
+    }
 }
@@ -0,0 +1,70 @@
+// SPDX-FileCopyrightText: 2022 jerusdp
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+mod helper;
+
+use claims::{assert_err, assert_ok};
+use hcaptcha::{Code, Error, Hcaptcha};
+use serde_json::json;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const ADDRESS: &str = "127.0.0.1:18431";
+
+#[derive(Hcaptcha)]
+#[hcaptcha(
+    secret_env = "TEST_CONTAINER_HCAPTCHA_SECRET",
+    url = "http://127.0.0.1:18431/siteverify",
+    hostnames = ["example.com"],
+    max_score = 0.5
+)]
+struct Test {
+    #[captcha]
+    hcaptcha: String,
+}
+
+#[tokio::main]
+async fn main() {
+    // Setup
+    let secret = format!("0x{}", hex::encode(helper::random_string(20)));
+
+    let listener = std::net::TcpListener::bind(ADDRESS).unwrap();
+    let mock_server = MockServer::builder().listener(listener).start().await;
+    Mock::given(method("POST"))
+        .and(path("/siteverify"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "success": true,
+            "hostname": "example.com",
+            "score": 0.2,
+        })))
+        .up_to_n_times(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/siteverify"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "success": true,
+            "hostname": "other.example",
+            "score": 0.9,
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let form = Test {
+        hcaptcha: helper::random_string(100),
+    };
+
+    // The secret is read from the environment when verifying.
+    assert_err!(form.verify().await);
+    std::env::set_var("TEST_CONTAINER_HCAPTCHA_SECRET", &secret);
+
+    assert_ok!(form.verify().await);
+    match form.verify().await {
+        Err(Error::Codes(codes)) => {
+            assert!(codes.contains(&Code::HostnameMismatch));
+            assert!(codes.contains(&Code::ScoreThresholdExceeded));
+        }
+        _ => panic!("Expected the policy to reject the response"),
+    }
+}
@@ -0,0 +1,80 @@
+// SPDX-FileCopyrightText: 2022 jerusdp
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+mod helper;
+
+use claims::assert_ok;
+use hcaptcha::{Client, Hcaptcha};
+use serde_json::json;
+use wiremock::matchers::{body_string_contains, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[derive(Hcaptcha)]
+enum Submission {
+    Signup {
+        #[allow(dead_code)]
+        email: String,
+        #[captcha]
+        token: String,
+        #[remoteip]
+        ip: String,
+    },
+    Login(#[allow(dead_code)] String, #[captcha] String),
+}
+
+#[derive(Hcaptcha)]
+struct Token(#[captcha] String);
+
+#[tokio::main]
+async fn main() {
+    // Setup
+    let secret = format!("0x{}", hex::encode(helper::random_string(20)));
+    let signup_token = helper::random_string(100);
+    let login_token = helper::random_string(100);
+    let newtype_token = helper::random_string(100);
+
+    let mock_server = MockServer::start().await;
+    for (token, remoteip) in [
+        (&signup_token, Some("192.168.0.17")),
+        (&login_token, None),
+        (&newtype_token, None),
+    ] {
+        let mut body = format!("response={token}");
+        if let Some(remoteip) = remoteip {
+            body = format!("{body}&remoteip={remoteip}");
+        }
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .and(body_string_contains(body))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "success": true,
+                "hostname": "test-host",
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+    }
+
+    let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+    let client = Client::new_with(&uri).unwrap();
+
+    // Each variant verifies its own captcha field.
+    let signup = Submission::Signup {
+        email: "user@example.com".to_string(),
+        token: signup_token,
+        ip: "192.168.0.17".to_string(),
+    };
+    let response = signup.valid_response_with(&client, &secret).await;
+    assert_ok!(&response);
+
+    let login = Submission::Login("user".to_string(), login_token);
+    let response = login.valid_response(&secret, Some(uri)).await;
+    assert_ok!(&response);
+
+    // A tuple struct marks its captcha field by position.
+    let newtype = Token(newtype_token);
+    let response = newtype.valid_response_with(&client, &secret).await;
+    assert_ok!(&response);
+    assert!(response.unwrap().success());
+}
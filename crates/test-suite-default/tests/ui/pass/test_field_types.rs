@@ -0,0 +1,87 @@
+// SPDX-FileCopyrightText: 2022 jerusdp
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+mod helper;
+
+use claims::assert_ok;
+use hcaptcha::Hcaptcha;
+use serde_json::json;
+use std::borrow::Cow;
+use std::net::IpAddr;
+use uuid::Uuid;
+use wiremock::matchers::{body_string, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[derive(Hcaptcha)]
+struct Borrowed<'a> {
+    #[captcha]
+    hcaptcha: &'a str,
+    #[remoteip]
+    ip: Option<IpAddr>,
+    #[sitekey]
+    key: Uuid,
+}
+
+#[derive(Hcaptcha)]
+struct Owned {
+    #[captcha]
+    hcaptcha: Cow<'static, str>,
+    #[remoteip]
+    ip: Option<String>,
+    #[sitekey]
+    key: Option<Uuid>,
+}
+
+#[tokio::main]
+async fn main() {
+    // Setup
+    let token = helper::random_string(100);
+    let secret = format!("0x{}", hex::encode(helper::random_string(20)));
+    let ip: IpAddr = "192.0.2.10".parse().unwrap();
+    let key = Uuid::new_v4();
+
+    let expected_body = format!(
+        "response={}&remoteip={}&sitekey={}&secret={}",
+        &token, ip, key, &secret
+    );
+    let expected_body_without_remoteip = format!("response={}&secret={}", &token, &secret);
+
+    let response_template = ResponseTemplate::new(200).set_body_json(json!({
+        "success": true,
+        "hostname": "test-host",
+    }));
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/siteverify"))
+        .and(body_string(&expected_body))
+        .respond_with(response_template.clone())
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/siteverify"))
+        .and(body_string(&expected_body_without_remoteip))
+        .respond_with(response_template)
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+
+    let form = Borrowed {
+        hcaptcha: &token,
+        ip: Some(ip),
+        key,
+    };
+    assert_ok!(form.valid_response(&secret, Some(uri.clone())).await);
+
+    // Fields that are None are not sent.
+    let form = Owned {
+        hcaptcha: Cow::Owned(token.clone()),
+        ip: None,
+        key: None,
+    };
+    assert_ok!(form.valid_response(&secret, Some(uri)).await);
+}
@@ -0,0 +1,106 @@
+// SPDX-FileCopyrightText: 2022 jerusdp
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+mod helper;
+
+use claims::assert_ok;
+use hcaptcha::{Client, Code, Error, Hcaptcha};
+use serde_json::json;
+use wiremock::matchers::{body_string_contains, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const ADDRESS: &str = "127.0.0.1:18432";
+
+#[derive(Hcaptcha)]
+struct CaptchaFields {
+    #[captcha]
+    token: String,
+    #[sitekey]
+    sitekey: String,
+}
+
+#[derive(Hcaptcha)]
+struct Signup {
+    #[allow(dead_code)]
+    email: String,
+    #[hcaptcha(flatten)]
+    captcha: CaptchaFields,
+}
+
+#[derive(Hcaptcha)]
+#[hcaptcha(
+    secret_env = "TEST_FLATTEN_HCAPTCHA_SECRET",
+    url = "http://127.0.0.1:18432/siteverify",
+    max_score = 0.5
+)]
+enum Submission {
+    Contact {
+        #[allow(dead_code)]
+        message: String,
+        #[hcaptcha(flatten)]
+        captcha: CaptchaFields,
+    },
+    Login(#[captcha] String),
+}
+
+#[tokio::main]
+async fn main() {
+    // Setup
+    let secret = format!("0x{}", hex::encode(helper::random_string(20)));
+    let sitekey = mockd::unique::uuid_v4();
+
+    let listener = std::net::TcpListener::bind(ADDRESS).unwrap();
+    let mock_server = MockServer::builder().listener(listener).start().await;
+    Mock::given(method("POST"))
+        .and(path("/siteverify"))
+        .and(body_string_contains(format!("sitekey={sitekey}")))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "success": true,
+            "hostname": "test-host",
+            "score": 0.2,
+        })))
+        .expect(3)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/siteverify"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "success": true,
+            "hostname": "test-host",
+            "score": 0.9,
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+    let captcha = || CaptchaFields {
+        token: helper::random_string(100),
+        sitekey: sitekey.clone(),
+    };
+
+    // The outer struct delegates to the flattened fields.
+    let signup = Signup {
+        email: "user@example.com".to_string(),
+        captcha: captcha(),
+    };
+    assert_ok!(signup.valid_response(&secret, Some(uri.clone())).await);
+    let client = Client::new_with(&uri).unwrap();
+    assert_ok!(signup.valid_response_with(&client, &secret).await);
+
+    // The container settings of the outer type apply to the flattened
+    // fields.
+    std::env::set_var("TEST_FLATTEN_HCAPTCHA_SECRET", &secret);
+    let contact = Submission::Contact {
+        message: "Hello".to_string(),
+        captcha: captcha(),
+    };
+    assert_ok!(contact.verify().await);
+
+    let login = Submission::Login(helper::random_string(100));
+    match login.verify().await {
+        Err(Error::Codes(codes)) => assert!(codes.contains(&Code::ScoreThresholdExceeded)),
+        _ => panic!("Expected the score to be rejected"),
+    }
+}
@@ -0,0 +1,53 @@
+// SPDX-FileCopyrightText: 2022 jerusdp
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+mod helper;
+
+use claims::assert_ok;
+use hcaptcha::{Client, Hcaptcha};
+use serde_json::json;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[derive(Hcaptcha)]
+struct Test {
+    #[captcha]
+    hcaptcha: String,
+    #[remoteip]
+    ip: String,
+}
+
+#[tokio::main]
+async fn main() {
+    // Setup
+    let secret = format!("0x{}", hex::encode(helper::random_string(20)));
+
+    let response_template = ResponseTemplate::new(200).set_body_json(json!({
+        "success": true,
+        "hostname": "test-host",
+    }));
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/siteverify"))
+        .respond_with(response_template)
+        .expect(3)
+        .mount(&mock_server)
+        .await;
+
+    let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+
+    // One client verifies every form.
+    let client = Client::new_with(&uri).unwrap();
+    for _ in 0..3 {
+        let form = Test {
+            hcaptcha: helper::random_string(100),
+            ip: mockd::internet::ipv4_address(),
+        };
+        let response = form.valid_response_with(&client, &secret).await;
+
+        assert_ok!(&response);
+        assert!(&response.unwrap().success());
+    }
+}
@@ -0,0 +1,46 @@
+// SPDX-FileCopyrightText: 2022 jerusdp
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+mod helper;
+
+use claims::assert_ok;
+use hcaptcha::Hcaptcha;
+use serde_json::json;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[derive(Hcaptcha)]
+struct Test {
+    #[captcha]
+    hcaptcha: String,
+}
+
+#[tokio::main(flavor = "multi_thread")]
+async fn main() {
+    // Setup
+    let token = helper::random_string(100);
+    let secret = format!("0x{}", hex::encode(helper::random_string(20)));
+
+    let response_template = ResponseTemplate::new(200).set_body_json(json!({
+        "success": true,
+        "hostname": "test-host",
+    }));
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/siteverify"))
+        .respond_with(response_template)
+        .mount(&mock_server)
+        .await;
+
+    let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+
+    // The future is Send so the verification can run on another thread.
+    let form = Test { hcaptcha: token };
+    let verification = form.valid_response(&secret, Some(uri));
+    let response = tokio::spawn(verification).await.unwrap();
+
+    assert_ok!(&response);
+    assert!(&response.unwrap().success());
+}
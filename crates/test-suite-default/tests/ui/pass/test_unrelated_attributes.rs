@@ -0,0 +1,52 @@
+// SPDX-FileCopyrightText: 2022 jerusdp
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+mod helper;
+
+use claims::assert_ok;
+use hcaptcha::Hcaptcha;
+use serde_json::json;
+use wiremock::matchers::{body_string, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Attributes for other macros and tools are ignored by the derive.
+#[derive(Hcaptcha)]
+struct Test {
+    /// The response token.
+    #[allow(dead_code)]
+    #[captcha]
+    hcaptcha: String,
+    #[rustfmt::skip]
+    #[hcaptcha(remoteip)]
+    ip: String,
+}
+
+#[tokio::main]
+async fn main() {
+    // Setup
+    let token = helper::random_string(100);
+    let secret = format!("0x{}", hex::encode(helper::random_string(20)));
+    let ip = "192.0.2.10".to_string();
+
+    let expected_body = format!("response={}&remoteip={}&secret={}", &token, &ip, &secret);
+
+    let response_template = ResponseTemplate::new(200).set_body_json(json!({
+        "success": true,
+        "hostname": "test-host",
+    }));
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/siteverify"))
+        .and(body_string(&expected_body))
+        .respond_with(response_template)
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+
+    let form = Test { hcaptcha: token, ip };
+    assert_ok!(form.valid_response(&secret, Some(uri)).await);
+}
@@ -6,9 +6,9 @@ use hcaptcha::Hcaptcha;
 // use hcaptcha_derive::Hcaptcha;
 
 #[derive(Hcaptcha)]
-enum Test {
+union Test {
     #[captcha]
-    Hcaptcha(String),
+    hcaptcha: u64,
 }
 
 fn main() {
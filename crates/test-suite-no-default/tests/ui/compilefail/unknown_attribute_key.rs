@@ -0,0 +1,18 @@
+// SPDX-FileCopyrightText: 2022 jerusdp
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use hcaptcha::Hcaptcha;
+
+#[derive(Hcaptcha)]
+#[hcaptcha(secret_env = "HCAPTCHA_SECRET", timeout = 5)]
+struct Test {
+    #[captcha]
+    hcaptcha: String,
+    #[hcaptcha(remote_ip)]
+    ip: String,
+}
+
+fn main() {
+    println!("Super!");
+}
@@ -0,0 +1,21 @@
+// SPDX-FileCopyrightText: 2022 jerusdp
+//
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use hcaptcha::Hcaptcha;
+
+#[derive(Hcaptcha)]
+struct Test {
+    #[captcha]
+    hcaptcha: String,
+    #[captcha]
+    #[allow(dead_code)]
+    other: String,
+    #[remoteip]
+    #[sitekey]
+    ip: String,
+}
+
+fn main() {
+    println!("Super!");
+}